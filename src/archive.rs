@@ -0,0 +1,153 @@
+// Copyright 2025 Maya Kaczorowski
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use chrono::{DateTime, NaiveDate, Utc};
+use csv::Writer;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+static MANIFEST: &str = "manifest.csv";
+static MANIFEST_HEADER: [&str; 4] = ["ID", "Captured At", "File", "SHA-256"];
+
+/// Suffix of the full page captures, which the offline parser reads back.
+static PAGE_SUFFIX: &str = ".page.html";
+
+/// Keeps a copy of what each product page said at scrape time. Files are
+/// named `<id>_<timestamp>_<hash>.<kind>` and every one is listed, with its
+/// full SHA-256, in `manifest.csv` in the same directory.
+#[derive(Clone)]
+pub struct Archive {
+    dir: PathBuf,
+    manifest: Arc<Mutex<Writer<File>>>,
+}
+
+fn sha256_hex(content: &str) -> String {
+    Sha256::digest(content.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// IDs come from user input, so keep only characters that are safe in a
+/// file name.
+fn file_safe(id: &str) -> String {
+    id.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+impl Archive {
+    /// Opens `dir`, creating it if needed, and appends to its manifest.
+    pub fn open(dir: &str) -> io::Result<Self> {
+        let dir = PathBuf::from(dir);
+        fs::create_dir_all(&dir)?;
+
+        let path = dir.join(MANIFEST);
+        let manifest = if path.exists() {
+            Writer::from_writer(OpenOptions::new().append(true).open(&path)?)
+        } else {
+            let mut w = Writer::from_writer(File::create(&path)?);
+            w.write_record(MANIFEST_HEADER)?;
+            w
+        };
+
+        Ok(Archive {
+            dir,
+            manifest: Arc::new(Mutex::new(manifest)),
+        })
+    }
+
+    /// Saves one capture of `id`: each `(kind, content)` pair becomes a file,
+    /// all stamped with the same capture time.
+    pub fn save(&self, id: &str, files: &[(&str, &str)]) -> io::Result<()> {
+        let now = Utc::now();
+        let stamp = now.format("%Y%m%dT%H%M%SZ");
+        let captured_at = now.to_rfc3339();
+
+        for (kind, content) in files {
+            let hash = sha256_hex(content);
+            let name = format!("{}_{}_{}.{}", file_safe(id), stamp, &hash[..12], kind);
+            fs::write(self.dir.join(&name), content)?;
+
+            let mut manifest = self.manifest.lock().unwrap();
+            manifest.write_record([id, &captured_at, &name, &hash])?;
+            manifest.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether `dir` is an archive written by [`Archive`].
+pub fn is_archive(dir: &str) -> bool {
+    Path::new(dir).join(MANIFEST).is_file()
+}
+
+/// Parses `--as-of`: an RFC 3339 time, or a date meaning the end of that day
+/// (UTC).
+pub fn parse_as_of(s: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map(|date| date.and_hms_opt(23, 59, 59).unwrap().and_utc())
+        .map_err(|_| format!("{:?} is neither a YYYY-MM-DD date nor an RFC 3339 time", s))
+}
+
+/// The latest page capture of each product in the archive at `dir`, by
+/// product ID. With `as_of`, later captures are ignored, so older snapshots
+/// can be parsed again.
+pub fn latest_pages(
+    dir: &str,
+    as_of: Option<DateTime<Utc>>,
+) -> Result<BTreeMap<String, PathBuf>, Box<dyn Error + Send + Sync>> {
+    let dir = Path::new(dir);
+    let mut latest: BTreeMap<String, (DateTime<Utc>, PathBuf)> = BTreeMap::new();
+    for record in csv::Reader::from_path(dir.join(MANIFEST))?.records() {
+        let record = record?;
+        let (Some(id), Some(captured_at), Some(file)) =
+            (record.get(0), record.get(1), record.get(2))
+        else {
+            continue;
+        };
+        if !file.ends_with(PAGE_SUFFIX) {
+            continue;
+        }
+        let captured_at = DateTime::parse_from_rfc3339(captured_at)
+            .map_err(|e| format!("{}: bad capture time {:?}: {}", MANIFEST, captured_at, e))?
+            .with_timezone(&Utc);
+        if as_of.is_some_and(|as_of| captured_at > as_of) {
+            continue;
+        }
+        if latest
+            .get(id)
+            .is_none_or(|(previous, _)| captured_at >= *previous)
+        {
+            latest.insert(id.to_string(), (captured_at, dir.join(file)));
+        }
+    }
+    Ok(latest
+        .into_iter()
+        .map(|(id, (_, path))| (id, path))
+        .collect())
+}
@@ -0,0 +1,290 @@
+// Copyright 2025 Maya Kaczorowski
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Maps the marketplace's JSON data file onto [`Product`]. Keys are the ones
+//! the file uses (see `tests/fixtures/data.json`), compared ignoring case and
+//! punctuation so a change such as `auth_date` to `AuthDate` still matches.
+use crate::product::{
+    AgencyAuthorization, AgencyRole, AuthorizationDetails, Milestone, Product, ProductOverview,
+};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::error::Error;
+
+const ID_KEY: &str = "id";
+
+const PROVIDER_KEY: &str = "csp";
+const OFFERING_KEY: &str = "name";
+const SERVICE_MODEL_KEY: &str = "service_model";
+const DEPLOYMENT_MODEL_KEY: &str = "deployment_model";
+const IMPACT_LEVEL_KEY: &str = "impact_level";
+const STATUS_KEY: &str = "status";
+
+const ASSESSOR_KEY: &str = "independent_assessor";
+const READY_KEY: &str = "ready_date";
+const AE_REVIEW_KEY: &str = "authorizing_entity_review";
+const PMO_REVIEW_KEY: &str = "pmo_review";
+const AUTHORIZED_KEY: &str = "auth_date";
+const ANNUAL_KEY: &str = "annual_assessment";
+
+const SPONSOR_KEY: &str = "partnering_agency";
+const REUSE_KEY: &str = "reuses";
+const AGENCY_NAME_KEY: &str = "agency";
+const AGENCY_DATE_KEY: &str = "date";
+
+/// Lowercases and drops everything but letters and digits, so
+/// `Service Model`, `service_model` and `serviceModel` compare equal.
+fn normalize(key: &str) -> String {
+    key.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// The non-null value stored under `key`.
+fn lookup<'a>(object: &'a Map<String, Value>, key: &str) -> Option<&'a Value> {
+    let key = normalize(key);
+    object
+        .iter()
+        .find(|(k, v)| normalize(k) == key && !v.is_null())
+        .map(|(_, v)| v)
+}
+
+/// A scalar or list of scalars as display text; lists are joined with ", ".
+fn text(value: &Value) -> Option<String> {
+    let text = match value {
+        Value::String(s) => s.trim().to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Array(items) => items.iter().filter_map(text).collect::<Vec<_>>().join(", "),
+        _ => return None,
+    };
+    Some(text).filter(|t| !t.is_empty())
+}
+
+fn field(object: &Map<String, Value>, key: &str) -> Option<String> {
+    lookup(object, key).and_then(text)
+}
+
+/// Dates in the data file are usually ISO timestamps; only the date part is
+/// kept.
+fn milestone(object: &Map<String, Value>, key: &str) -> Option<Milestone> {
+    field(object, key).map(|raw| match raw.split_once('T') {
+        Some((date, _)) if date.len() == 10 => Milestone::parse(date.to_string()),
+        _ => Milestone::parse(raw),
+    })
+}
+
+fn agencies(object: &Map<String, Value>, key: &str, role: AgencyRole) -> Vec<AgencyAuthorization> {
+    let entries = match lookup(object, key) {
+        Some(Value::Array(items)) => items.iter().collect(),
+        Some(value) => vec![value],
+        None => Vec::new(),
+    };
+    entries
+        .into_iter()
+        .filter_map(|entry| {
+            let (agency, date) = match entry {
+                Value::Object(entry) => (
+                    field(entry, AGENCY_NAME_KEY)?,
+                    milestone(entry, AGENCY_DATE_KEY).map(|m| m.raw),
+                ),
+                other => (text(other)?, None),
+            };
+            Some(AgencyAuthorization { agency, role, date })
+        })
+        .collect()
+}
+
+/// Maps one product object from the data file.
+pub fn parse_product(id: &str, product: &Value) -> Product {
+    let empty = Map::new();
+    let object = product.as_object().unwrap_or(&empty);
+
+    let overview = ProductOverview {
+        provider_name: field(object, PROVIDER_KEY),
+        offering_name: field(object, OFFERING_KEY),
+        service_model: field(object, SERVICE_MODEL_KEY),
+        deployment_model: field(object, DEPLOYMENT_MODEL_KEY),
+        impact_level: field(object, IMPACT_LEVEL_KEY),
+        status: field(object, STATUS_KEY),
+    };
+    let authorization = AuthorizationDetails {
+        fedramp_ready: milestone(object, READY_KEY),
+        authorizing_entity_review: milestone(object, AE_REVIEW_KEY),
+        pmo_review: milestone(object, PMO_REVIEW_KEY),
+        fedramp_authorized: milestone(object, AUTHORIZED_KEY),
+        annual_assessment: milestone(object, ANNUAL_KEY),
+        independent_assessor: field(object, ASSESSOR_KEY),
+    };
+    let mut agencies_list = agencies(object, SPONSOR_KEY, AgencyRole::Sponsor);
+    agencies_list.extend(agencies(object, REUSE_KEY, AgencyRole::Reuse));
+
+    Product {
+        id: id.to_string(),
+        overview,
+        authorization,
+        agencies: agencies_list,
+        extras: BTreeMap::new(),
+        redirect: None,
+    }
+}
+
+/// Finds the product list in the data file, which is either the whole
+/// document or a `Products` array under `data`, and keys it by product ID in
+/// file order.
+pub fn products_by_id(body: Value) -> Result<Map<String, Value>, Box<dyn Error + Send + Sync>> {
+    let list = match body {
+        Value::Array(list) => list,
+        Value::Object(mut root) => {
+            let mut root = match root.remove("data") {
+                Some(Value::Object(data)) => data,
+                _ => root,
+            };
+            let key = root
+                .keys()
+                .find(|k| normalize(k) == "products")
+                .cloned()
+                .ok_or("no Products list in the data file")?;
+            match root.remove(&key) {
+                Some(Value::Array(list)) => list,
+                _ => return Err("Products in the data file is not a list".into()),
+            }
+        }
+        _ => return Err("data file is neither a JSON object nor a list".into()),
+    };
+
+    let mut products = Map::new();
+    for product in list {
+        let Some(id) = product.as_object().and_then(|p| field(p, ID_KEY)) else {
+            continue;
+        };
+        products.insert(id, product);
+    }
+    Ok(products)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> Map<String, Value> {
+        let body = serde_json::from_str(include_str!("../tests/fixtures/data.json")).unwrap();
+        products_by_id(body).unwrap()
+    }
+
+    #[test]
+    fn finds_products_by_id_in_file_order() {
+        let products = fixture();
+        let ids: Vec<&str> = products.keys().map(String::as_str).collect();
+        assert_eq!(ids, ["FR1234567890", "FR2345678901"]);
+    }
+
+    #[test]
+    fn maps_an_authorized_product() {
+        let products = fixture();
+        let product = parse_product("FR1234567890", &products["FR1234567890"]);
+        let overview = &product.overview;
+        let auth = &product.authorization;
+
+        assert_eq!(overview.offering_name.as_deref(), Some("Acme Cloud"));
+        assert_eq!(overview.provider_name.as_deref(), Some("Acme Corp"));
+        assert_eq!(overview.service_model.as_deref(), Some("SaaS, PaaS"));
+        assert_eq!(overview.deployment_model.as_deref(), Some("Public Cloud"));
+        assert_eq!(overview.impact_level.as_deref(), Some("Moderate"));
+        assert_eq!(overview.status.as_deref(), Some("FedRAMP Authorized"));
+
+        assert_eq!(
+            auth.independent_assessor.as_deref(),
+            Some("Coalfire Systems")
+        );
+        assert!(auth.fedramp_ready.is_none());
+        let milestones: Vec<(&str, String)> = auth
+            .milestones()
+            .into_iter()
+            .filter_map(|(label, m)| m.map(|m| (label, m.iso())))
+            .collect();
+        assert_eq!(
+            milestones,
+            [
+                ("Authorizing Entity Review", "2020-11-02".to_string()),
+                ("PMO Review", "2021-01-04".to_string()),
+                ("FedRAMP Authorized", "2021-03-05".to_string()),
+                ("Annual Assessment", "2024-03-01".to_string()),
+            ]
+        );
+        // Only the date part of a timestamp is kept as the raw value.
+        assert_eq!(auth.fedramp_authorized.as_ref().unwrap().raw, "2021-03-05");
+
+        let agencies: Vec<_> = product
+            .agencies
+            .iter()
+            .map(|a| (a.agency.as_str(), a.role, a.date.as_deref()))
+            .collect();
+        assert_eq!(
+            agencies,
+            [
+                ("Department of Energy", AgencyRole::Sponsor, None),
+                (
+                    "Department of Commerce",
+                    AgencyRole::Reuse,
+                    Some("2022-06-07")
+                ),
+                ("General Services Administration", AgencyRole::Reuse, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn maps_a_ready_product_with_null_fields() {
+        let products = fixture();
+        let product = parse_product("FR2345678901", &products["FR2345678901"]);
+        let auth = &product.authorization;
+
+        assert_eq!(product.overview.status.as_deref(), Some("FedRAMP Ready"));
+        assert_eq!(auth.fedramp_ready.as_ref().unwrap().iso(), "2023-09-14");
+        assert!(auth.fedramp_authorized.is_none());
+        assert!(auth.annual_assessment.is_none());
+        assert!(product.agencies.is_empty());
+    }
+
+    #[test]
+    fn keys_match_ignoring_case_and_punctuation() {
+        let product = parse_product(
+            "FR1",
+            &serde_json::json!({
+                "CSP": "Acme Corp",
+                "AuthDate": "03/05/2021",
+                "Reuses": ["NASA", ""],
+            }),
+        );
+        assert_eq!(product.overview.provider_name.as_deref(), Some("Acme Corp"));
+        let authorized = product.authorization.fedramp_authorized.unwrap();
+        assert_eq!(authorized.raw, "03/05/2021");
+        assert_eq!(authorized.iso(), "2021-03-05");
+        assert_eq!(product.agencies.len(), 1);
+        assert_eq!(product.agencies[0].agency, "NASA");
+    }
+
+    #[test]
+    fn product_list_shapes() {
+        let list = serde_json::json!([{ "id": "FR1" }, { "id": 2 }]);
+        let ids: Vec<String> = products_by_id(list).unwrap().keys().cloned().collect();
+        assert_eq!(ids, ["FR1", "2"]);
+
+        let err = products_by_id(serde_json::json!({ "data": { "Providers": [] } })).unwrap_err();
+        assert!(err.to_string().contains("no Products list"), "{}", err);
+        assert!(products_by_id(serde_json::json!("text")).is_err());
+    }
+}
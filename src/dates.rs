@@ -0,0 +1,54 @@
+// Copyright 2025 Maya Kaczorowski
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use chrono::{Datelike, NaiveDate};
+
+/// Date formats seen on the marketplace, most common first.
+static FORMATS: [&str; 5] = ["%m/%d/%Y", "%Y-%m-%d", "%B %d, %Y", "%b %d, %Y", "%m/%d/%y"];
+
+/// Parses a milestone value such as "03/14/2022" or "March 14, 2022".
+/// Returns `None` for anything that isn't a recognizable calendar date.
+pub fn parse_date(raw: &str) -> Option<NaiveDate> {
+    let raw = raw.trim();
+    FORMATS
+        .iter()
+        .filter_map(|format| NaiveDate::parse_from_str(raw, format).ok())
+        .find(|date| date.year() >= 1900)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepted_and_rejected_formats() {
+        let cases = [
+            ("03/14/2022", Some((2022, 3, 14))),
+            ("  03/14/2022 ", Some((2022, 3, 14))),
+            ("2022-03-14", Some((2022, 3, 14))),
+            ("March 14, 2022", Some((2022, 3, 14))),
+            ("Mar 14, 2022", Some((2022, 3, 14))),
+            // "%m/%d/%Y" reads this as the year 22, which is skipped in
+            // favor of the two-digit-year format.
+            ("3/4/22", Some((2022, 3, 4))),
+            ("03/14/2022 (Revoked)", None),
+            ("02/30/2022", None),
+            ("N/A", None),
+            ("", None),
+        ];
+        for (raw, expected) in cases {
+            let expected = expected.map(|(y, m, d)| NaiveDate::from_ymd_opt(y, m, d).unwrap());
+            assert_eq!(parse_date(raw), expected, "{:?}", raw);
+        }
+    }
+}
@@ -0,0 +1,151 @@
+// Copyright 2025 Maya Kaczorowski
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::output::{Table, read_table};
+use std::collections::BTreeMap;
+use std::error::Error;
+
+/// Changes from run to run (and is missing or zero in older outputs), so it
+/// is never reported as a change.
+static IGNORED_COLUMNS: [&str; 1] = ["Attempts"];
+
+/// Compares two outputs of this tool and prints the products that were added
+/// or removed, that failed or recovered, and every field that changed. Only
+/// columns present in both files are compared, so outputs from older versions
+/// can still be diffed. Fields of a product whose scrape failed in either run
+/// aren't compared, since its blank columns say nothing about the product.
+pub fn run(old_path: &str, new_path: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let old = read_table(old_path)?;
+    let new = read_table(new_path)?;
+
+    let columns: Vec<&String> = new
+        .header
+        .iter()
+        .skip(1)
+        .filter(|c| old.header.contains(c) && !IGNORED_COLUMNS.contains(&c.as_str()))
+        .collect();
+    let only_in = |a: &Table, b: &Table| -> Vec<String> {
+        a.header
+            .iter()
+            .filter(|c| !b.header.contains(c) && !IGNORED_COLUMNS.contains(&c.as_str()))
+            .cloned()
+            .collect()
+    };
+    for column in only_in(&new, &old) {
+        eprintln!("Column only in {}: {}", new_path, column);
+    }
+    for column in only_in(&old, &new) {
+        eprintln!("Column only in {}: {}", old_path, column);
+    }
+
+    let old_rows = by_id(&old);
+    let new_rows = by_id(&new);
+
+    let added: Vec<&&str> = new_rows
+        .keys()
+        .filter(|id| !old_rows.contains_key(*id))
+        .collect();
+    let removed: Vec<&&str> = old_rows
+        .keys()
+        .filter(|id| !new_rows.contains_key(*id))
+        .collect();
+
+    println!("Added ({}):", added.len());
+    for id in &added {
+        println!("  + {}", id);
+    }
+    println!("Removed ({}):", removed.len());
+    for id in &removed {
+        println!("  - {}", id);
+    }
+
+    let mut failed = Vec::new();
+    let mut recovered = Vec::new();
+    let mut changed = Vec::new();
+    for (id, new_row) in &new_rows {
+        let Some(old_row) = old_rows.get(id) else {
+            continue;
+        };
+        if is_error(new_row) {
+            failed.push((id, new_row));
+            continue;
+        }
+        if is_error(old_row) {
+            recovered.push(id);
+            continue;
+        }
+        let changes: Vec<(&String, &str, &str)> = columns
+            .iter()
+            .map(|&c| {
+                (
+                    c,
+                    old_row.get(c.as_str()).copied().unwrap_or_default(),
+                    new_row.get(c.as_str()).copied().unwrap_or_default(),
+                )
+            })
+            .filter(|(_, old_value, new_value)| old_value != new_value)
+            .collect();
+        if !changes.is_empty() {
+            changed.push((id, changes));
+        }
+    }
+
+    println!("Failed ({}):", failed.len());
+    for (id, row) in &failed {
+        let field = |c: &str| row.get(c).copied().unwrap_or_default();
+        println!(
+            "  ! {}: {}: {}",
+            id,
+            field("Error Kind"),
+            field("Error Message")
+        );
+    }
+    println!("Recovered ({}):", recovered.len());
+    for id in &recovered {
+        println!("  * {}", id);
+    }
+
+    println!("Changed ({}):", changed.len());
+    for (id, changes) in changed {
+        println!("  {}", id);
+        for (column, old_value, new_value) in changes {
+            println!("    {}: {:?} -> {:?}", column, old_value, new_value);
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether a row records a failed scrape rather than a product.
+fn is_error(row: &BTreeMap<&str, &str>) -> bool {
+    row.get("Scrape Status") == Some(&"error")
+}
+
+/// Indexes rows by product ID. If an ID appears more than once the last row
+/// wins, matching what a resumed run would report.
+fn by_id(table: &Table) -> BTreeMap<&str, BTreeMap<&str, &str>> {
+    table
+        .rows
+        .iter()
+        .filter_map(|row| {
+            let id = row.first()?.as_str();
+            let fields = table
+                .header
+                .iter()
+                .map(String::as_str)
+                .zip(row.iter().map(String::as_str))
+                .collect();
+            Some((id, fields))
+        })
+        .collect()
+}
@@ -0,0 +1,153 @@
+// Copyright 2025 Maya Kaczorowski
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+use thirtyfour::prelude::*;
+
+static LISTING_URL: &str = "https://marketplace.fedramp.gov/products";
+
+static CARD_SELECTOR: &str = "a[href*='/products/']";
+static NEXT_PAGE_SELECTOR: &str = "button[aria-label*='next' i]";
+
+/// Statuses shown on the listing cards, used to tell the status line apart
+/// from the product name.
+static STATUSES: [&str; 3] = ["FedRAMP Authorized", "FedRAMP In Process", "FedRAMP Ready"];
+
+/// How long to wait for the listing to re-render after moving to the next page.
+const PAGE_CHANGE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug)]
+pub struct ListingEntry {
+    pub id: String,
+    pub name: String,
+    pub status: String,
+}
+
+/// The product ID in a marketplace product URL or link, if it is one.
+pub fn product_id_from_href(href: &str) -> Option<String> {
+    let path = href.split(['?', '#']).next()?;
+    let (_, rest) = path.split_once("/products/")?;
+    let id = rest.trim_end_matches('/');
+    if id.is_empty() || id.contains('/') {
+        return None;
+    }
+    Some(id.to_string())
+}
+
+fn parse_card(id: String, text: &str) -> ListingEntry {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect();
+    let status = lines
+        .iter()
+        .find_map(|line| STATUSES.iter().find(|s| line.contains(**s)))
+        .map(|s| s.to_string())
+        .unwrap_or_default();
+    let name = lines
+        .iter()
+        .find(|line| !STATUSES.iter().any(|s| line.contains(s)))
+        .map(|s| s.to_string())
+        .unwrap_or_default();
+
+    ListingEntry { id, name, status }
+}
+
+async fn first_card_href(driver: &WebDriver) -> Option<String> {
+    let card = driver.find(By::Css(CARD_SELECTOR)).await.ok()?;
+    card.attr("href").await.ok().flatten()
+}
+
+/// Crawls the marketplace product listing, following pagination until no new
+/// products show up, and returns every product found in listing order.
+pub async fn discover_products(
+    driver: &WebDriver,
+) -> Result<Vec<ListingEntry>, Box<dyn Error + Send + Sync>> {
+    driver.goto(LISTING_URL).await?;
+
+    let mut entries = Vec::new();
+    let mut seen = HashSet::new();
+    let mut page = 1;
+
+    loop {
+        driver.query(By::Css(CARD_SELECTOR)).first().await?;
+        let cards = driver.find_all(By::Css(CARD_SELECTOR)).await?;
+
+        let mut new_on_page = 0;
+        for card in cards {
+            let Some(id) = card
+                .attr("href")
+                .await?
+                .as_deref()
+                .and_then(product_id_from_href)
+            else {
+                continue;
+            };
+            if !seen.insert(id.clone()) {
+                continue;
+            }
+
+            // The link often only wraps the product name, so read the whole
+            // card when one can be found.
+            let container = card
+                .find(By::XPath("./ancestor::*[contains(@class,'card')][1]"))
+                .await
+                .unwrap_or(card);
+            let text = container.text().await.unwrap_or_default();
+            entries.push(parse_card(id, &text));
+            new_on_page += 1;
+        }
+        eprintln!("Listing page {}: {} new products", page, new_on_page);
+
+        if new_on_page == 0 {
+            break;
+        }
+
+        let next = match driver.find(By::Css(NEXT_PAGE_SELECTOR)).await {
+            Ok(button) if button.is_enabled().await.unwrap_or(false) => button,
+            _ => break,
+        };
+
+        let before = first_card_href(driver).await;
+        next.click().await?;
+
+        let started = tokio::time::Instant::now();
+        while first_card_href(driver).await == before && started.elapsed() < PAGE_CHANGE_TIMEOUT {
+            tokio::time::sleep(Duration::from_millis(250)).await;
+        }
+        page += 1;
+    }
+
+    Ok(entries)
+}
+
+/// Writes discovered IDs in the same one-ID-per-line format accepted by
+/// `--input`, with each product's name and status as a trailing comment.
+pub fn write_id_file<P: AsRef<Path>>(path: P, entries: &[ListingEntry]) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    for entry in entries {
+        match (entry.name.is_empty(), entry.status.is_empty()) {
+            (true, true) => writeln!(out, "{}", entry.id)?,
+            (false, true) => writeln!(out, "{}  # {}", entry.id, entry.name)?,
+            (true, false) => writeln!(out, "{}  # ({})", entry.id, entry.status)?,
+            (false, false) => writeln!(out, "{}  # {} ({})", entry.id, entry.name, entry.status)?,
+        }
+    }
+    out.flush()
+}
@@ -0,0 +1,108 @@
+// Copyright 2025 Maya Kaczorowski
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use std::error::Error;
+use std::net::{TcpListener, TcpStream};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// How long a freshly started driver gets to start accepting connections.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// chromedriver/geckodriver processes started by this tool, one per session
+/// since geckodriver only serves a single session. They are killed when this
+/// is dropped, which also happens while unwinding from a panic; `main` drops
+/// it on Ctrl-C once the sessions have been ended.
+pub struct ManagedDrivers {
+    children: Mutex<Vec<Child>>,
+    pub ports: Vec<u16>,
+}
+
+impl ManagedDrivers {
+    pub async fn spawn(binary: &str, count: usize) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let mut drivers = ManagedDrivers {
+            children: Mutex::new(Vec::new()),
+            ports: Vec::new(),
+        };
+
+        for _ in 0..count {
+            let port = free_port()?;
+            let child = Command::new(binary)
+                .arg(format!("--port={}", port))
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+                .map_err(|e| format!("Could not start {}: {}", binary, e))?;
+            drivers.lock().push(child);
+
+            drivers.wait_until_ready(port).await?;
+            eprintln!("Started {} on port {}", binary, port);
+            drivers.ports.push(port);
+        }
+
+        Ok(drivers)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Child>> {
+        self.children.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Waits for the most recently started driver to accept connections on `port`.
+    async fn wait_until_ready(&self, port: u16) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let started = Instant::now();
+        loop {
+            if let Some(status) = self
+                .lock()
+                .last_mut()
+                .and_then(|c| c.try_wait().ok().flatten())
+            {
+                return Err(format!("WebDriver process exited during startup ({})", status).into());
+            }
+            if TcpStream::connect(("127.0.0.1", port)).is_ok() {
+                return Ok(());
+            }
+            if started.elapsed() > STARTUP_TIMEOUT {
+                return Err(format!(
+                    "WebDriver process did not start listening on port {} within {}s",
+                    port,
+                    STARTUP_TIMEOUT.as_secs()
+                )
+                .into());
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+}
+
+impl Drop for ManagedDrivers {
+    fn drop(&mut self) {
+        kill_all(&self.children);
+    }
+}
+
+fn kill_all(children: &Mutex<Vec<Child>>) {
+    let mut children = children.lock().unwrap_or_else(PoisonError::into_inner);
+    for child in children.iter_mut() {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+    children.clear();
+}
+
+/// Asks the OS for an unused port. The listener is closed before the driver
+/// binds it, so another process could take it in between, but that window is
+/// tiny in practice.
+fn free_port() -> std::io::Result<u16> {
+    Ok(TcpListener::bind(("127.0.0.1", 0))?.local_addr()?.port())
+}
@@ -0,0 +1,332 @@
+// Copyright 2025 Maya Kaczorowski
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Merges scraped results back into the CSV the IDs came from, such as a
+//! vendor inventory, so the FedRAMP columns sit on the rows they belong to.
+use crate::input::{find_column, normalize};
+use crate::output::Table;
+use crate::product::CSV_HEADER;
+use csv::{ReaderBuilder, Writer};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::Write;
+
+/// Columns that are still written for rows whose scrape failed. The rest are
+/// left alone, so one failed run doesn't wipe out data from the last one.
+static STATUS_COLUMNS: [&str; 3] = ["Scrape Status", "Error Kind", "Error Message"];
+
+/// Output columns written into the enriched file: all but the ID, which the
+/// file already has, and the attempt count.
+fn merged_columns() -> impl Iterator<Item = &'static str> {
+    CSV_HEADER
+        .iter()
+        .copied()
+        .filter(|c| !matches!(*c, "ID" | "Attempts"))
+}
+
+/// The name an output column gets in the enriched file. Every one starts
+/// with "FedRAMP", which keeps them apart from generic columns such as
+/// "Status" or "Notes"; a file that has one of these names already is only
+/// accepted if an earlier enrichment wrote it (see [`Enrichment::open`]).
+fn enriched_name(column: &str) -> String {
+    if column.starts_with("FedRAMP ") {
+        column.to_string()
+    } else {
+        format!("FedRAMP {}", column)
+    }
+}
+
+/// A CSV being enriched, held in memory until it is written back.
+pub struct Enrichment {
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+    id_column: usize,
+    /// Spreadsheet exports often start with a byte order mark; it is written
+    /// back so they still open the same way.
+    bom: bool,
+}
+
+impl Enrichment {
+    /// Reads the CSV at `path` and finds its ID column, so a bad file is
+    /// reported before anything is scraped. A file with a column named like
+    /// an enriched one (say its own "FedRAMP Authorized" Yes/No column) is
+    /// refused, unless it has a "FedRAMP Scrape Status" column showing that
+    /// an earlier enrichment added them, in which case they are updated.
+    pub fn open(path: &str, id_column: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let contents = fs::read_to_string(path)?;
+        let bom = contents.starts_with('\u{feff}');
+        let contents = contents.trim_start_matches('\u{feff}');
+        if matches!(contents.trim_start().chars().next(), Some('[' | '{')) {
+            return Err(format!("{}: only CSV files can be enriched", path).into());
+        }
+
+        let mut rdr = ReaderBuilder::new()
+            .flexible(true)
+            .from_reader(contents.as_bytes());
+        let header: Vec<String> = rdr.headers()?.iter().map(String::from).collect();
+        let id_column = find_column(&header, id_column).map_err(|e| format!("{}: {}", path, e))?;
+        let enriched_before = header.contains(&enriched_name("Scrape Status"));
+        if !enriched_before
+            && let Some(clash) = merged_columns()
+                .map(enriched_name)
+                .find(|name| header.contains(name))
+        {
+            return Err(format!(
+                "{}: has its own {:?} column, which --enrich would overwrite; rename it first",
+                path, clash
+            )
+            .into());
+        }
+        let rows = rdr
+            .records()
+            .map(|r| r.map(|r| r.iter().map(String::from).collect()))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Enrichment {
+            header,
+            rows,
+            id_column,
+            bom,
+        })
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Fills the FedRAMP columns of every row whose ID has a result in
+    /// `table`. Columns left by an earlier enrichment are updated in place
+    /// and the others appended. Returns how many rows were
+    /// updated.
+    pub fn merge(&mut self, table: &Table) -> usize {
+        let mut columns = Vec::new();
+        for name in merged_columns() {
+            // Outputs from older versions may not have every column.
+            let Some(from) = table.header.iter().position(|h| h == name) else {
+                continue;
+            };
+            let enriched = enriched_name(name);
+            let to = match self.header.iter().position(|h| *h == enriched) {
+                Some(to) => to,
+                None => {
+                    self.header.push(enriched);
+                    self.header.len() - 1
+                }
+            };
+            columns.push((name, from, to));
+        }
+        let status = table.header.iter().position(|h| h == "Scrape Status");
+
+        // Later rows win, e.g. a retried ID appended by a resumed run.
+        let results: HashMap<&str, &Vec<String>> = table
+            .rows
+            .iter()
+            .map(|row| (row[0].as_str(), row))
+            .collect();
+
+        let mut updated = 0;
+        for row in &mut self.rows {
+            if row.len() < self.header.len() {
+                row.resize(self.header.len(), String::new());
+            }
+            let Ok(Some(id)) = normalize(&row[self.id_column]) else {
+                continue;
+            };
+            let Some(result) = results.get(id.as_str()) else {
+                continue;
+            };
+
+            let failed = status.and_then(|i| result.get(i)).map(String::as_str) == Some("error");
+            for (name, from, to) in &columns {
+                if failed && !STATUS_COLUMNS.contains(name) {
+                    continue;
+                }
+                row[*to] = result.get(*from).cloned().unwrap_or_default();
+            }
+            updated += 1;
+        }
+        updated
+    }
+
+    /// Writes the enriched CSV to `path`. It goes to a temporary file first,
+    /// so a failed write never leaves the original half overwritten.
+    pub fn write(&self, path: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let tmp = format!("{}.tmp", path);
+        let mut file = File::create(&tmp)?;
+        if self.bom {
+            file.write_all("\u{feff}".as_bytes())?;
+        }
+
+        let mut wtr = Writer::from_writer(file);
+        wtr.write_record(&self.header)?;
+        for row in &self.rows {
+            wtr.write_record(row)?;
+        }
+        wtr.flush()?;
+        drop(wtr);
+
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Writes `contents` to a file in the temp directory unique to this test.
+    fn inventory(name: &str, contents: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("fedramp-scraper-{}-{}", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    /// An output row for `id` with `values` in the named columns.
+    fn result(id: &str, values: &[(&str, &str)]) -> Vec<String> {
+        CSV_HEADER
+            .iter()
+            .map(|column| match *column {
+                "ID" => id.to_string(),
+                column => values
+                    .iter()
+                    .find(|(c, _)| *c == column)
+                    .map(|(_, v)| v.to_string())
+                    .unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    fn table(rows: Vec<Vec<String>>) -> Table {
+        Table {
+            header: CSV_HEADER.iter().map(|h| h.to_string()).collect(),
+            rows,
+        }
+    }
+
+    fn ok(id: &str, authorized: &str) -> Vec<String> {
+        result(
+            id,
+            &[("FedRAMP Authorized", authorized), ("Scrape Status", "ok")],
+        )
+    }
+
+    /// The rows of the CSV at `path`, header first.
+    fn read(path: &PathBuf) -> Vec<Vec<String>> {
+        csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_path(path)
+            .unwrap()
+            .records()
+            .map(|r| r.unwrap().iter().map(String::from).collect())
+            .collect()
+    }
+
+    fn column(rows: &[Vec<String>], name: &str) -> Vec<String> {
+        let i = rows[0].iter().position(|h| h == name).unwrap();
+        rows[1..].iter().map(|row| row[i].clone()).collect()
+    }
+
+    #[test]
+    fn matches_url_ids_and_skips_empty_ones() {
+        let path = inventory(
+            "urls.csv",
+            "Vendor,Product\n\
+             Acme,https://marketplace.fedramp.gov/products/FR1\n\
+             Nobody,\n\
+             Beta,FR2\n",
+        );
+        let mut enrichment = Enrichment::open(path.to_str().unwrap(), "product").unwrap();
+        let updated = enrichment.merge(&table(vec![ok("FR1", "2021-03-05"), ok("FR2", "")]));
+        assert_eq!(updated, 2);
+        enrichment.write(path.to_str().unwrap()).unwrap();
+
+        let rows = read(&path);
+        assert_eq!(column(&rows, "Vendor"), ["Acme", "Nobody", "Beta"]);
+        assert_eq!(column(&rows, "FedRAMP Authorized"), ["2021-03-05", "", ""]);
+        assert_eq!(column(&rows, "FedRAMP Scrape Status"), ["ok", "", "ok"]);
+        // Every row is padded to the new header.
+        assert!(rows.iter().all(|row| row.len() == rows[0].len()));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn second_run_reuses_columns_and_keeps_data_on_error() {
+        let path = inventory("rerun.csv", "Vendor,ID,Status\nAcme,FR1,Active\n");
+        let path_str = path.to_str().unwrap();
+
+        let mut enrichment = Enrichment::open(path_str, "ID").unwrap();
+        enrichment.merge(&table(vec![ok("FR1", "2021-03-05")]));
+        enrichment.write(path_str).unwrap();
+        let first = fs::read_to_string(&path).unwrap();
+        let header = read(&path).swap_remove(0);
+
+        // The same results again change nothing.
+        let mut enrichment = Enrichment::open(path_str, "ID").unwrap();
+        enrichment.merge(&table(vec![ok("FR1", "2021-03-05")]));
+        enrichment.write(path_str).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), first);
+
+        // A failed scrape updates the status columns only.
+        let failed = result(
+            "FR1",
+            &[
+                ("Scrape Status", "error"),
+                ("Error Kind", "timeout"),
+                ("Error Message", "timed out"),
+            ],
+        );
+        let mut enrichment = Enrichment::open(path_str, "ID").unwrap();
+        enrichment.merge(&table(vec![failed]));
+        enrichment.write(path_str).unwrap();
+
+        let rows = read(&path);
+        assert_eq!(rows[0], header);
+        assert_eq!(column(&rows, "Status"), ["Active"]);
+        assert_eq!(column(&rows, "FedRAMP Authorized"), ["2021-03-05"]);
+        assert_eq!(column(&rows, "FedRAMP Scrape Status"), ["error"]);
+        assert_eq!(column(&rows, "FedRAMP Error Message"), ["timed out"]);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn refuses_a_column_of_the_files_own() {
+        let path = inventory("clash.csv", "ID,FedRAMP Authorized\nFR1,Yes\n");
+        let err = Enrichment::open(path.to_str().unwrap(), "ID")
+            .err()
+            .unwrap();
+        assert!(
+            err.to_string().contains("\"FedRAMP Authorized\""),
+            "{}",
+            err
+        );
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn keeps_a_byte_order_mark() {
+        let path = inventory("bom.csv", "\u{feff}ID,Vendor\nFR1,Acme\n");
+        let mut enrichment = Enrichment::open(path.to_str().unwrap(), "ID").unwrap();
+        assert_eq!(enrichment.merge(&table(vec![ok("FR1", "")])), 1);
+        enrichment.write(path.to_str().unwrap()).unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents.starts_with("\u{feff}ID,Vendor,FedRAMP Ready,"));
+        assert_eq!(contents.matches('\u{feff}').count(), 1);
+        fs::remove_file(path).unwrap();
+    }
+}
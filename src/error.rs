@@ -0,0 +1,82 @@
+// Copyright 2025 Maya Kaczorowski
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+
+/// Why a product could not be scraped, as reported in the `Error Kind`
+/// column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// The page, file or data could not be loaded at all.
+    Navigation,
+    /// The marketplace has no product with this ID.
+    NotFound,
+    /// The page loaded but never finished rendering.
+    Timeout,
+    /// None of the sections the field map reads from (Authorization
+    /// Details by default) is on the page.
+    SectionNotFound,
+    /// The sections the field map reads from have no content.
+    EmptySection,
+    /// The content was there but could not be read.
+    Parse,
+    /// Anything else, including errors read back from older outputs.
+    Other,
+}
+
+impl ErrorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorKind::Navigation => "navigation",
+            ErrorKind::NotFound => "not_found",
+            ErrorKind::Timeout => "timeout",
+            ErrorKind::SectionNotFound => "section_not_found",
+            ErrorKind::EmptySection => "empty_section",
+            ErrorKind::Parse => "parse",
+            ErrorKind::Other => "other",
+        }
+    }
+}
+
+/// A failed scrape: what kind of failure it was and the details.
+#[derive(Debug, Clone)]
+pub struct ScrapeError {
+    pub kind: ErrorKind,
+    pub message: String,
+}
+
+impl ScrapeError {
+    pub fn new(kind: ErrorKind, message: impl fmt::Display) -> Self {
+        ScrapeError {
+            kind,
+            message: message.to_string(),
+        }
+    }
+
+    /// Whether trying again could give a different result. A missing product
+    /// stays missing.
+    pub fn is_retryable(&self) -> bool {
+        self.kind != ErrorKind::NotFound
+    }
+}
+
+impl fmt::Display for ScrapeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for ScrapeError {}
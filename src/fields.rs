@@ -0,0 +1,339 @@
+// Copyright 2025 Maya Kaczorowski
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Which page labels fill which output columns. The built-in mapping covers
+//! every labelled field we know about; `--field-config` replaces it with one
+//! read from a TOML file, e.g.
+//!
+//! ```toml
+//! [[field]]
+//! section = "Authorization Details"
+//! label = "PMO Review"
+//! column = "PMO Review"
+//! type = "date"
+//! ```
+//!
+//! `section` is the heading of the section the label appears in; leave it out
+//! to match the label anywhere on the page. `type` defaults to the column's
+//! own type. A `column` that isn't one of the built-in output columns is
+//! kept in the product's extras under that name, as text unless `type` is
+//! `date`, in which case the extra holds the date as YYYY-MM-DD and
+//! `<column> (Raw)` the text it was read from.
+use crate::product::{
+    AgencyAuthorization, AuthorizationDetails, Milestone, Product, ProductOverview,
+};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::fs;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldType {
+    Text,
+    Date,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FieldSpec {
+    pub section: Option<String>,
+    pub label: String,
+    pub column: String,
+    #[serde(rename = "type")]
+    pub kind: Option<FieldType>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FieldConfig {
+    field: Vec<FieldSpec>,
+}
+
+/// Output columns that can be filled from a label, with their types.
+static COLUMNS: [(&str, FieldType); 12] = [
+    ("FedRAMP Ready", FieldType::Date),
+    ("Authorizing Entity Review", FieldType::Date),
+    ("PMO Review", FieldType::Date),
+    ("FedRAMP Authorized", FieldType::Date),
+    ("Annual Assessment", FieldType::Date),
+    ("Independent Assessor", FieldType::Text),
+    ("Cloud Service Provider", FieldType::Text),
+    ("Cloud Service Offering", FieldType::Text),
+    ("Service Model", FieldType::Text),
+    ("Deployment Model", FieldType::Text),
+    ("Impact Level", FieldType::Text),
+    ("Status", FieldType::Text),
+];
+
+pub static AUTH_SECTION: &str = "Authorization Details";
+
+/// `(section, label, column)` for the built-in mapping.
+static DEFAULT_FIELDS: [(Option<&str>, &str, &str); 12] = [
+    (
+        Some(AUTH_SECTION),
+        "Independent Assessor",
+        "Independent Assessor",
+    ),
+    (Some(AUTH_SECTION), "FedRAMP Ready", "FedRAMP Ready"),
+    (
+        Some(AUTH_SECTION),
+        "Authorizing Entity Review",
+        "Authorizing Entity Review",
+    ),
+    (Some(AUTH_SECTION), "PMO Review", "PMO Review"),
+    (
+        Some(AUTH_SECTION),
+        "FedRAMP Authorized",
+        "FedRAMP Authorized",
+    ),
+    (Some(AUTH_SECTION), "Annual Assessment", "Annual Assessment"),
+    (None, "Cloud Service Provider", "Cloud Service Provider"),
+    (None, "Cloud Service Offering", "Cloud Service Offering"),
+    (None, "Service Model", "Service Model"),
+    (None, "Deployment Model", "Deployment Model"),
+    (None, "Impact Level", "Impact Level"),
+    (None, "Status", "Status"),
+];
+
+/// The label-to-column mapping used by both page parsers.
+#[derive(Debug, Clone)]
+pub struct FieldMap {
+    pub fields: Vec<FieldSpec>,
+}
+
+impl Default for FieldMap {
+    fn default() -> Self {
+        FieldMap {
+            fields: DEFAULT_FIELDS
+                .iter()
+                .map(|(section, label, column)| FieldSpec {
+                    section: section.map(String::from),
+                    label: label.to_string(),
+                    column: column.to_string(),
+                    kind: None,
+                })
+                .collect(),
+        }
+    }
+}
+
+fn column_type(column: &str) -> Option<FieldType> {
+    COLUMNS
+        .iter()
+        .find(|(name, _)| *name == column)
+        .map(|(_, kind)| *kind)
+}
+
+impl FieldSpec {
+    /// The declared type, else the column's own, else text.
+    pub fn field_type(&self) -> FieldType {
+        self.kind
+            .or_else(|| column_type(&self.column))
+            .unwrap_or(FieldType::Text)
+    }
+}
+
+impl FieldMap {
+    /// Reads a mapping from a TOML file, checking that fields for built-in
+    /// columns don't declare a different type.
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let config: FieldConfig =
+            toml::from_str(&fs::read_to_string(path)?).map_err(|e| format!("{}: {}", path, e))?;
+
+        for field in &config.field {
+            if field.column.trim().is_empty() {
+                return Err(format!("{}: empty column for label {:?}", path, field.label).into());
+            }
+            if let Some(expected) = column_type(&field.column)
+                && field.kind.is_some_and(|kind| kind != expected)
+            {
+                return Err(format!(
+                    "{}: column {:?} is of type {:?}",
+                    path, field.column, expected
+                )
+                .into());
+            }
+            if field.label.trim().is_empty() {
+                return Err(format!("{}: empty label for column {:?}", path, field.column).into());
+            }
+        }
+
+        Ok(FieldMap {
+            fields: config.field,
+        })
+    }
+
+    /// Headings of every section some field is read from.
+    pub fn sections(&self) -> Vec<&str> {
+        let mut sections: Vec<&str> = Vec::new();
+        for section in self.fields.iter().filter_map(|f| f.section.as_deref()) {
+            if !sections.contains(&section) {
+                sections.push(section);
+            }
+        }
+        sections
+    }
+
+    /// Every column the mapping can fill, in mapping order.
+    pub fn columns(&self) -> Vec<&str> {
+        let mut columns: Vec<&str> = Vec::new();
+        for field in &self.fields {
+            if !columns.contains(&field.column.as_str()) {
+                columns.push(&field.column);
+            }
+        }
+        columns
+    }
+}
+
+/// Fields read from one page, before they are put together into a
+/// [`Product`].
+#[derive(Debug, Default)]
+pub struct ParsedFields {
+    pub authorization: AuthorizationDetails,
+    pub overview: ProductOverview,
+    pub extras: BTreeMap<String, String>,
+}
+
+impl ParsedFields {
+    pub fn into_product(self, id: &str, agencies: Vec<AgencyAuthorization>) -> Product {
+        Product {
+            id: id.to_string(),
+            overview: self.overview,
+            authorization: self.authorization,
+            agencies,
+            extras: self.extras,
+            redirect: None,
+        }
+    }
+}
+
+/// Stores `value` in the product field behind `field`'s column, or in the
+/// extras if it isn't a built-in column.
+pub fn assign(parsed: &mut ParsedFields, field: &FieldSpec, value: String) {
+    let ParsedFields {
+        authorization,
+        overview,
+        extras,
+    } = parsed;
+
+    match field.column.as_str() {
+        "FedRAMP Ready" => authorization.fedramp_ready = Some(Milestone::parse(value)),
+        "Authorizing Entity Review" => {
+            authorization.authorizing_entity_review = Some(Milestone::parse(value))
+        }
+        "PMO Review" => authorization.pmo_review = Some(Milestone::parse(value)),
+        "FedRAMP Authorized" => authorization.fedramp_authorized = Some(Milestone::parse(value)),
+        "Annual Assessment" => authorization.annual_assessment = Some(Milestone::parse(value)),
+        "Independent Assessor" => authorization.independent_assessor = Some(value),
+        "Cloud Service Provider" => overview.provider_name = Some(value),
+        "Cloud Service Offering" => overview.offering_name = Some(value),
+        "Service Model" => overview.service_model = Some(value),
+        "Deployment Model" => overview.deployment_model = Some(value),
+        "Impact Level" => overview.impact_level = Some(value),
+        "Status" => overview.status = Some(value),
+        column if field.field_type() == FieldType::Date => {
+            let date = Milestone::parse(value);
+            extras.insert(raw_column(column), date.raw.clone());
+            extras.insert(column.to_string(), date.iso());
+        }
+        column => {
+            extras.insert(column.to_string(), value);
+        }
+    }
+}
+
+/// The extra holding the page text of a date extra.
+fn raw_column(column: &str) -> String {
+    format!("{} (Raw)", column)
+}
+
+/// Whether `product` has a value for `column`.
+fn has_column(product: &Product, column: &str) -> bool {
+    let auth = &product.authorization;
+    let overview = &product.overview;
+    match column {
+        "FedRAMP Ready" => auth.fedramp_ready.is_some(),
+        "Authorizing Entity Review" => auth.authorizing_entity_review.is_some(),
+        "PMO Review" => auth.pmo_review.is_some(),
+        "FedRAMP Authorized" => auth.fedramp_authorized.is_some(),
+        "Annual Assessment" => auth.annual_assessment.is_some(),
+        "Independent Assessor" => auth.independent_assessor.is_some(),
+        "Cloud Service Provider" => overview.provider_name.is_some(),
+        "Cloud Service Offering" => overview.offering_name.is_some(),
+        "Service Model" => overview.service_model.is_some(),
+        "Deployment Model" => overview.deployment_model.is_some(),
+        "Impact Level" => overview.impact_level.is_some(),
+        "Status" => overview.status.is_some(),
+        _ => product.extras.contains_key(column),
+    }
+}
+
+/// Tracks, over a run, labels the field map doesn't know and columns it never
+/// managed to fill. Either usually means the site's layout has changed.
+#[derive(Debug, Default)]
+pub struct DriftReport {
+    products: usize,
+    new_labels: BTreeMap<String, usize>,
+    filled: HashSet<String>,
+}
+
+impl DriftReport {
+    pub fn record(&mut self, map: &FieldMap, product: &Product) {
+        self.products += 1;
+        let columns = map.columns();
+        let raw_columns: Vec<String> = columns.iter().map(|c| raw_column(c)).collect();
+        for label in product.extras.keys() {
+            if !columns.contains(&label.as_str()) && !raw_columns.contains(label) {
+                *self.new_labels.entry(label.clone()).or_default() += 1;
+            }
+        }
+        for column in columns {
+            if !self.filled.contains(column) && has_column(product, column) {
+                self.filled.insert(column.to_string());
+            }
+        }
+    }
+
+    /// Prints the labels seen but not mapped, and the mapped columns that
+    /// were never filled. Prints nothing if there was no drift.
+    pub fn print(&self, map: &FieldMap) {
+        if self.products == 0 {
+            return;
+        }
+        let missing: Vec<&str> = map
+            .columns()
+            .into_iter()
+            .filter(|column| !self.filled.contains(*column))
+            .collect();
+        if self.new_labels.is_empty() && missing.is_empty() {
+            return;
+        }
+
+        eprintln!(
+            "Warning: the page layout may have changed ({} products checked)",
+            self.products
+        );
+        for (label, count) in &self.new_labels {
+            eprintln!(
+                "  New label {:?} (seen on {} of {} products)",
+                label, count, self.products
+            );
+        }
+        for column in missing {
+            eprintln!("  Expected field {:?} was never found", column);
+        }
+    }
+}
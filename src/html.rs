@@ -0,0 +1,377 @@
+// Copyright 2025 Maya Kaczorowski
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::archive;
+use crate::error::{ErrorKind, ScrapeError};
+use crate::fields::FieldMap;
+use crate::parse::{self, PageText};
+use crate::product::Product;
+use chrono::{DateTime, Utc};
+use scraper::{ElementRef, Html, Node, Selector};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+static H1: LazyLock<Selector> = LazyLock::new(|| Selector::parse("h1").unwrap());
+static HEADINGS: LazyLock<Selector> = LazyLock::new(|| Selector::parse("h1, h2").unwrap());
+static H3: LazyLock<Selector> = LazyLock::new(|| Selector::parse("h3").unwrap());
+static LI: LazyLock<Selector> = LazyLock::new(|| Selector::parse("li").unwrap());
+static BODY: LazyLock<Selector> = LazyLock::new(|| Selector::parse("body").unwrap());
+
+/// Elements that start a new line in the rendered text.
+static LINE_BREAKS: [&str; 9] = ["br", "div", "p", "li", "dt", "dd", "tr", "h3", "h4"];
+
+/// Elements that lay out other blocks. One of these with no block inside it
+/// (a `<div>` holding just a value, say) is read as a block itself.
+static BLOCK_ELEMENTS: [&str; 20] = [
+    "address", "article", "aside", "dd", "div", "dl", "dt", "footer", "header", "li", "main",
+    "nav", "ol", "p", "section", "table", "td", "th", "tr", "ul",
+];
+
+/// Headings, which name a section rather than hold its content.
+static HEADING_ELEMENTS: [&str; 6] = ["h1", "h2", "h3", "h4", "h5", "h6"];
+
+/// Elements whose text is never shown.
+static HIDDEN_ELEMENTS: [&str; 4] = ["script", "style", "template", "noscript"];
+
+/// Text of an element approximating what the browser reports as its rendered
+/// text: whitespace collapsed, with a line break at each `<br>` and block
+/// element.
+fn text_of(element: ElementRef) -> String {
+    let mut raw = String::new();
+    for node in element.descendants().skip(1) {
+        match node.value() {
+            Node::Text(text) => raw.extend(
+                text.chars()
+                    .map(|c| if c.is_whitespace() { ' ' } else { c }),
+            ),
+            Node::Element(e) if LINE_BREAKS.contains(&e.name()) => raw.push('\n'),
+            _ => {}
+        }
+    }
+    raw.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Text of each innermost block in `element`, in document order, leaving out
+/// headings. Siblings that aren't blocks, like a `<span>` or bare text next to
+/// a label's `<p>`, each count as a block of their own, so a label and its
+/// value can be told apart however they are marked up.
+fn blocks(element: ElementRef) -> Vec<String> {
+    let mut found = Vec::new();
+    for child in element.children() {
+        match child.value() {
+            Node::Text(text) => {
+                let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+                if !text.is_empty() {
+                    found.push(text);
+                }
+            }
+            Node::Element(e) => {
+                let name = e.name();
+                if HEADING_ELEMENTS.contains(&name) || HIDDEN_ELEMENTS.contains(&name) {
+                    continue;
+                }
+                let child = ElementRef::wrap(child).unwrap();
+                let has_blocks = child.descendants().skip(1).any(|node| {
+                    node.value()
+                        .as_element()
+                        .is_some_and(|e| BLOCK_ELEMENTS.contains(&e.name()))
+                });
+                if has_blocks {
+                    found.extend(blocks(child));
+                } else {
+                    let text = text_of(child);
+                    if !text.is_empty() {
+                        found.push(text);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    found
+}
+
+/// Parents of every `<h3>` whose text contains `heading`, matching the
+/// `//h3[contains(text(),...)]/parent::div` lookups used against a live page.
+fn sections<'a>(
+    doc: &'a Html,
+    heading: &'a str,
+) -> impl Iterator<Item = (String, ElementRef<'a>)> + 'a {
+    doc.select(&H3).filter_map(move |h3| {
+        let text = text_of(h3);
+        if !text.contains(heading) {
+            return None;
+        }
+        let parent = h3.parent().and_then(ElementRef::wrap)?;
+        Some((text, parent))
+    })
+}
+
+/// Section headings for a message, e.g. "Authorization Details" or
+/// "Authorization Details or Overview".
+pub fn section_names(headings: &[&str]) -> String {
+    headings.join(" or ")
+}
+
+/// Parses a rendered product page, whether saved to disk or read from the
+/// browser. It must have at least one of the sections `fields` reads from,
+/// with some content.
+pub fn parse_product_page(id: &str, html: &str, fields: &FieldMap) -> Result<Product, ScrapeError> {
+    let doc = Html::parse_document(html);
+
+    if doc
+        .select(&HEADINGS)
+        .any(|h| text_of(h).to_lowercase().contains("not found"))
+    {
+        return Err(ScrapeError::new(
+            ErrorKind::NotFound,
+            format!("The page says product {} was not found", id),
+        ));
+    }
+
+    // The page has rendered once any section the field map reads from is
+    // there with content, so a renamed section only needs a new config.
+    let required = fields.sections();
+    if !required.is_empty() {
+        let found: Vec<ElementRef> = required
+            .iter()
+            .flat_map(|heading| sections(&doc, heading))
+            .map(|(_, section)| section)
+            .collect();
+        if found.is_empty() {
+            return Err(ScrapeError::new(
+                ErrorKind::SectionNotFound,
+                format!("{} section not found", section_names(&required)),
+            ));
+        }
+        if found.iter().all(|section| blocks(*section).is_empty()) {
+            return Err(ScrapeError::new(
+                ErrorKind::EmptySection,
+                format!("{} section has no content", section_names(&required)),
+            ));
+        }
+    }
+
+    let page = PageText {
+        sections: fields
+            .sections()
+            .into_iter()
+            .map(|heading| {
+                let texts = sections(&doc, heading)
+                    .flat_map(|(_, section)| blocks(section))
+                    .collect();
+                (heading.to_string(), texts)
+            })
+            .collect(),
+        blocks: doc.select(&BODY).next().map(blocks).unwrap_or_default(),
+        heading: doc.select(&H1).next().map(text_of),
+    };
+    let parsed = parse::parse_fields(fields, &page);
+
+    let mut agencies = Vec::new();
+    for (heading, section) in sections(&doc, "Agenc") {
+        let mut items: Vec<String> = section.select(&LI).map(text_of).collect();
+        if items.is_empty() {
+            items = blocks(section);
+        }
+        agencies.extend(parse::parse_agencies(
+            parse::agency_role(&heading),
+            items.iter().map(String::as_str),
+        ));
+    }
+
+    Ok(parsed.into_product(id, agencies))
+}
+
+/// Parses the saved page at `path`.
+pub fn parse_product_file(
+    path: &Path,
+    id: &str,
+    fields: &FieldMap,
+) -> Result<Product, ScrapeError> {
+    let html = fs::read_to_string(path).map_err(|e| {
+        ScrapeError::new(ErrorKind::Navigation, format!("{}: {}", path.display(), e))
+    })?;
+    parse_product_page(id, &html, fields)
+}
+
+/// The saved page of each product in `dir`, by product ID. `dir` is either an
+/// archive written with `--archive-dir`, whose latest page capture (at or
+/// before `as_of`) is used, or a directory of `<id>.html` files.
+pub fn pages_in_dir(
+    dir: &str,
+    as_of: Option<DateTime<Utc>>,
+) -> Result<BTreeMap<String, PathBuf>, Box<dyn Error + Send + Sync>> {
+    if archive::is_archive(dir) {
+        return archive::latest_pages(dir, as_of);
+    }
+    if as_of.is_some() {
+        return Err(format!("{} is not an archive, so --as-of does not apply", dir).into());
+    }
+
+    let mut pages = BTreeMap::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("html"))
+            && let Some(stem) = path.file_stem().and_then(|s| s.to_str())
+        {
+            pages.insert(stem.to_string(), path.clone());
+        }
+    }
+    Ok(pages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::product::AgencyRole;
+
+    static PRODUCT: &str = include_str!("../tests/fixtures/product.html");
+
+    fn parse(html: &str) -> Result<Product, ScrapeError> {
+        parse_product_page("FR123", html, &FieldMap::default())
+    }
+
+    #[test]
+    fn parses_saved_product_page() {
+        let product = parse(PRODUCT).unwrap();
+        let auth = &product.authorization;
+        let overview = &product.overview;
+
+        assert_eq!(product.id, "FR123");
+        assert_eq!(overview.offering_name.as_deref(), Some("Acme Cloud"));
+        assert_eq!(overview.provider_name.as_deref(), Some("Acme Corp"));
+        assert_eq!(overview.service_model.as_deref(), Some("SaaS"));
+        assert_eq!(overview.deployment_model.as_deref(), Some("Public Cloud"));
+        assert_eq!(overview.impact_level.as_deref(), Some("Moderate"));
+        assert_eq!(overview.status.as_deref(), Some("FedRAMP Authorized"));
+
+        assert_eq!(
+            auth.independent_assessor.as_deref(),
+            Some("Coalfire Systems")
+        );
+        assert_eq!(auth.fedramp_ready.as_ref().unwrap().iso(), "2020-01-02");
+        assert_eq!(auth.pmo_review.as_ref().unwrap().iso(), "2020-02-03");
+        let authorized = auth.fedramp_authorized.as_ref().unwrap();
+        assert_eq!(authorized.raw, "March 5, 2021");
+        assert_eq!(authorized.iso(), "2021-03-05");
+        assert_eq!(auth.annual_assessment.as_ref().unwrap().iso(), "2023-04-05");
+        assert!(auth.authorizing_entity_review.is_none());
+        assert!(product.extras.is_empty());
+
+        let agencies: Vec<_> = product
+            .agencies
+            .iter()
+            .map(|a| (a.agency.as_str(), a.role, a.date.as_deref()))
+            .collect();
+        assert_eq!(
+            agencies,
+            [
+                (
+                    "Department of Energy",
+                    AgencyRole::Sponsor,
+                    Some("03/05/2021")
+                ),
+                (
+                    "Department of Commerce",
+                    AgencyRole::Reuse,
+                    Some("06/07/2022")
+                ),
+                ("General Services Administration", AgencyRole::Reuse, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_values_from_sibling_elements() {
+        let product = parse(
+            "<html><body><h1>Acme Cloud</h1>\
+             <div><h3>Authorization Details</h3>\
+             <p>Independent Assessor</p><span>Coalfire Systems</span>\
+             <p>FedRAMP Authorized:</p><div>03/05/2021</div>\
+             <div><strong>PMO Review:</strong> <em>02/03/2020</em></div>\
+             </div></body></html>",
+        )
+        .unwrap();
+        let auth = &product.authorization;
+
+        assert_eq!(
+            auth.independent_assessor.as_deref(),
+            Some("Coalfire Systems")
+        );
+        assert_eq!(
+            auth.fedramp_authorized.as_ref().unwrap().iso(),
+            "2021-03-05"
+        );
+        assert_eq!(auth.pmo_review.as_ref().unwrap().iso(), "2020-02-03");
+    }
+
+    #[test]
+    fn not_found_page() {
+        let e = parse("<html><body><h1>Product Not Found</h1></body></html>").unwrap_err();
+        assert_eq!(e.kind, ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn missing_authorization_section() {
+        let e = parse("<html><body><h1>Acme Cloud</h1><p>Service Model: SaaS</p></body></html>")
+            .unwrap_err();
+        assert_eq!(e.kind, ErrorKind::SectionNotFound);
+    }
+
+    #[test]
+    fn empty_authorization_section() {
+        let e = parse(
+            "<html><body><h1>Acme Cloud</h1>\
+             <div><h3>Authorization Details</h3></div></body></html>",
+        )
+        .unwrap_err();
+        assert_eq!(e.kind, ErrorKind::EmptySection);
+    }
+
+    #[test]
+    fn renamed_section_from_field_config() {
+        let page = "<html><body><h1>Acme Cloud</h1>\
+                    <div><h3>Authorization Timeline</h3>\
+                    <p>PMO Review: 02/03/2020</p>\
+                    <p>Independent Assessor: Coalfire Systems</p></div></body></html>";
+        let mut fields = FieldMap::default();
+        for field in &mut fields.fields {
+            if field.section.is_some() {
+                field.section = Some("Authorization Timeline".to_string());
+            }
+        }
+
+        let product = parse_product_page("FR123", page, &fields).unwrap();
+        let auth = &product.authorization;
+        assert_eq!(auth.pmo_review.as_ref().unwrap().iso(), "2020-02-03");
+        assert_eq!(
+            auth.independent_assessor.as_deref(),
+            Some("Coalfire Systems")
+        );
+
+        // The built-in mapping still looks for the old heading.
+        let e = parse(page).unwrap_err();
+        assert_eq!(e.kind, ErrorKind::SectionNotFound);
+        assert_eq!(e.message, "Authorization Details section not found");
+    }
+}
@@ -0,0 +1,450 @@
+// Copyright 2025 Maya Kaczorowski
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::discover::product_id_from_href;
+use crate::product::CSV_HEADER;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read};
+use url::Url;
+
+/// An input line that could not be turned into a product ID.
+#[derive(Debug)]
+pub struct Rejected {
+    /// Where the line came from, e.g. `ids.txt:12` or `argument 2`.
+    pub location: String,
+    pub text: String,
+    pub reason: &'static str,
+}
+
+/// Product IDs gathered from every input, in first-seen order.
+#[derive(Debug, Default)]
+pub struct InputIds {
+    pub ids: Vec<String>,
+    pub rejected: Vec<Rejected>,
+    pub duplicates: usize,
+    seen: HashSet<String>,
+    /// Input columns to carry through to the output.
+    pub columns: Vec<String>,
+    /// Values of `columns` for each ID, from the first row it appeared on.
+    carried: HashMap<String, Vec<String>>,
+}
+
+/// The only host whose product URLs are accepted.
+static MARKETPLACE_HOST: &str = "marketplace.fedramp.gov";
+
+/// The product ID in a marketplace product URL, with or without its scheme.
+fn marketplace_id(entry: &str) -> Option<String> {
+    let url = if entry.contains("://") {
+        Url::parse(entry)
+    } else {
+        Url::parse(&format!("https://{}", entry))
+    }
+    .ok()?;
+    if !url.host_str()?.eq_ignore_ascii_case(MARKETPLACE_HOST) {
+        return None;
+    }
+    product_id_from_href(url.path())
+}
+
+/// Turns one input entry into a product ID. Entries may be a bare ID or a
+/// marketplace product URL; a `#` at the start or after whitespace begins a
+/// comment. Returns `Ok(None)` for entries that are blank or only a comment.
+pub fn normalize(entry: &str) -> Result<Option<String>, &'static str> {
+    let entry = entry.trim_start_matches('\u{feff}').trim();
+    // A `#` straight after other text is a URL fragment, not a comment.
+    let comment = entry
+        .char_indices()
+        .find(|&(i, c)| c == '#' && (i == 0 || entry[..i].ends_with(char::is_whitespace)));
+    let entry = match comment {
+        Some((i, _)) => entry[..i].trim(),
+        None => entry,
+    };
+    if entry.is_empty() {
+        return Ok(None);
+    }
+
+    let id = if entry.contains("://") || entry.contains("/products/") {
+        marketplace_id(entry).ok_or("not a marketplace product URL")?
+    } else {
+        entry.to_string()
+    };
+
+    if id.len() > 64 {
+        return Err("too long to be a product ID");
+    }
+    if !id
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    {
+        return Err("contains characters that can't appear in a product ID");
+    }
+    Ok(Some(id))
+}
+
+impl InputIds {
+    /// Adds one entry, recording it as rejected or duplicate if need be.
+    /// Returns whether it was a new ID.
+    pub fn add(&mut self, location: impl FnOnce() -> String, entry: &str) -> bool {
+        match normalize(entry) {
+            Ok(None) => false,
+            Ok(Some(id)) => {
+                if self.seen.insert(id.clone()) {
+                    self.ids.push(id);
+                    true
+                } else {
+                    self.duplicates += 1;
+                    false
+                }
+            }
+            Err(reason) => {
+                self.rejected.push(Rejected {
+                    location: location(),
+                    text: entry.trim().to_string(),
+                    reason,
+                });
+                false
+            }
+        }
+    }
+
+    /// Adds an entry read from a table row along with the row's values for
+    /// the carried columns.
+    fn add_row(&mut self, location: impl FnOnce() -> String, entry: &str, carried: Vec<String>) {
+        if self.add(location, entry) {
+            let id = self.ids.last().unwrap().clone();
+            self.carried.insert(id, carried);
+        }
+    }
+
+    /// The carried input columns for `id`, blank for IDs that didn't come
+    /// from a table.
+    pub fn carried(&self, id: &str) -> Vec<(String, String)> {
+        let values = self.carried.get(id);
+        self.columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                let value = values.and_then(|v| v.get(i)).cloned();
+                (column.clone(), value.unwrap_or_default())
+            })
+            .collect()
+    }
+
+    /// Adds every line of `path`, or of standard input if `path` is `-`.
+    pub fn add_file(&mut self, path: &str) -> io::Result<()> {
+        let reader: Box<dyn BufRead> = if path == "-" {
+            Box::new(io::stdin().lock())
+        } else {
+            Box::new(BufReader::new(File::open(path)?))
+        };
+        let name = if path == "-" { "stdin" } else { path };
+        for (i, line) in reader.lines().enumerate() {
+            self.add(|| format!("{}:{}", name, i + 1), &line?);
+        }
+        Ok(())
+    }
+
+    /// Adds the IDs in `column` of a CSV, JSON or JSON Lines file (or of
+    /// standard input if `path` is `-`), carrying the `carry` columns of each
+    /// row along. For JSON, columns are top-level keys or, starting with `/`,
+    /// JSON pointers into each record. Rows with no ID are skipped quietly,
+    /// since inventories often list vendors that have none.
+    pub fn add_table(
+        &mut self,
+        path: &str,
+        column: &str,
+        carry: &[String],
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if let Some(clash) = carry.iter().find(|c| CSV_HEADER.contains(&c.as_str())) {
+            return Err(format!("carried column {:?} is also an output column", clash).into());
+        }
+        self.columns = carry.to_vec();
+
+        let contents = if path == "-" {
+            let mut contents = String::new();
+            io::stdin().read_to_string(&mut contents)?;
+            contents
+        } else {
+            fs::read_to_string(path)?
+        };
+        let contents = contents.trim_start_matches('\u{feff}');
+        let name = if path == "-" { "stdin" } else { path };
+
+        if matches!(contents.trim_start().chars().next(), Some('[' | '{')) {
+            self.add_json(name, contents, column, carry)
+        } else {
+            self.add_csv(name, contents, column, carry)
+        }
+    }
+
+    fn add_csv(
+        &mut self,
+        name: &str,
+        contents: &str,
+        column: &str,
+        carry: &[String],
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut rdr = csv::ReaderBuilder::new()
+            .flexible(true)
+            .from_reader(contents.as_bytes());
+        let headers: Vec<String> = rdr.headers()?.iter().map(String::from).collect();
+        let id_column = find_column(&headers, column)?;
+        let carry_columns = carry
+            .iter()
+            .map(|c| find_column(&headers, c))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for record in rdr.records() {
+            let record = record?;
+            let line = record.position().map_or(0, |p| p.line());
+            let carried = carry_columns
+                .iter()
+                .map(|&i| record.get(i).unwrap_or_default().trim().to_string())
+                .collect();
+            self.add_row(
+                || format!("{}:{}", name, line),
+                record.get(id_column).unwrap_or_default(),
+                carried,
+            );
+        }
+        Ok(())
+    }
+
+    fn add_json(
+        &mut self,
+        name: &str,
+        contents: &str,
+        column: &str,
+        carry: &[String],
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        // Either one array of records or one record per line.
+        let mut records = Vec::new();
+        for value in serde_json::Deserializer::from_str(contents).into_iter::<Value>() {
+            match value? {
+                Value::Array(items) => records.extend(items),
+                record => records.push(record),
+            }
+        }
+
+        let mut found = false;
+        for (i, record) in records.iter().enumerate() {
+            let Some(id) = json_field(record, column) else {
+                continue;
+            };
+            found = true;
+            let carried = carry
+                .iter()
+                .map(|c| json_field(record, c).unwrap_or_default())
+                .collect();
+            self.add_row(|| format!("{}: record {}", name, i + 1), &id, carried);
+        }
+        if !found && !records.is_empty() {
+            return Err(format!("no record has a value for {:?}", column).into());
+        }
+        Ok(())
+    }
+
+    /// Prints what was skipped, so bad lines are noticed before the run
+    /// rather than as failed rows after it.
+    pub fn report(&self) {
+        if !self.rejected.is_empty() {
+            eprintln!("Skipping {} invalid input entries:", self.rejected.len());
+            for rejected in &self.rejected {
+                eprintln!(
+                    "  {}: {:?} ({})",
+                    rejected.location, rejected.text, rejected.reason
+                );
+            }
+        }
+        if self.duplicates > 0 {
+            eprintln!("Skipping {} duplicate IDs", self.duplicates);
+        }
+    }
+}
+
+/// The position of `wanted` in a CSV header, ignoring case and surrounding
+/// whitespace.
+pub fn find_column(headers: &[String], wanted: &str) -> Result<usize, String> {
+    headers
+        .iter()
+        .position(|h| h.trim().eq_ignore_ascii_case(wanted.trim()))
+        .ok_or_else(|| {
+            format!(
+                "no column {:?} (columns are {})",
+                wanted,
+                headers.join(", ")
+            )
+        })
+}
+
+/// The value of a top-level key or JSON pointer in `record`, as text.
+fn json_field(record: &Value, column: &str) -> Option<String> {
+    let value = if column.starts_with('/') {
+        record.pointer(column)
+    } else {
+        record.get(column)
+    };
+    match value? {
+        Value::Null => None,
+        Value::String(s) => Some(s.trim().to_string()),
+        other => Some(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(entry: &str) -> Option<String> {
+        normalize(entry).unwrap()
+    }
+
+    #[test]
+    fn bare_ids_and_comments() {
+        assert_eq!(id("FR1234567890"), Some("FR1234567890".to_string()));
+        assert_eq!(
+            id("  FR1234567890  # Acme"),
+            Some("FR1234567890".to_string())
+        );
+        assert_eq!(id("\u{feff}FR1"), Some("FR1".to_string()));
+        assert_eq!(id("# header"), None);
+        assert_eq!(id("   "), None);
+        assert!(normalize("FR1, FR2").is_err());
+    }
+
+    #[test]
+    fn product_urls() {
+        assert_eq!(
+            id("https://marketplace.fedramp.gov/products/FR123"),
+            Some("FR123".to_string())
+        );
+        assert_eq!(
+            id("https://marketplace.fedramp.gov/products/FR123/?tab=agencies#top"),
+            Some("FR123".to_string())
+        );
+        assert_eq!(
+            id("https://marketplace.fedramp.gov/products/FR123  # Acme"),
+            Some("FR123".to_string())
+        );
+        assert_eq!(
+            id("marketplace.fedramp.gov/products/FR123"),
+            Some("FR123".to_string())
+        );
+        assert!(normalize("https://example.com/products/FR123").is_err());
+        assert!(normalize("https://marketplace.fedramp.gov/about").is_err());
+    }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn csv_column_ignores_case() {
+        let mut input = InputIds::default();
+        input
+            .add_csv(
+                "inventory.csv",
+                "Vendor, FedRAMP ID ,Owner\nAcme,FR1,Ana\nNoID,,Bo\nBeta,FR2,Cy\n",
+                "fedramp id",
+                &strings(&["owner"]),
+            )
+            .unwrap();
+        assert_eq!(input.ids, ["FR1", "FR2"]);
+        assert!(input.rejected.is_empty());
+
+        input.columns = strings(&["owner"]);
+        assert_eq!(
+            input.carried("FR2"),
+            [("owner".to_string(), "Cy".to_string())]
+        );
+
+        let err = InputIds::default()
+            .add_csv("inventory.csv", "Vendor\nAcme\n", "ID", &[])
+            .unwrap_err();
+        assert!(err.to_string().contains("no column \"ID\""), "{}", err);
+    }
+
+    #[test]
+    fn json_lines_and_arrays() {
+        let lines = "{\"id\": \"FR1\", \"owner\": \"Ana\"}\n{\"id\": \"FR2\"}\n";
+        let array = "[{\"id\": \"FR1\", \"owner\": \"Ana\"}, {\"id\": \"FR2\"}]";
+        let carry = strings(&["owner"]);
+        for contents in [lines, array] {
+            let mut input = InputIds {
+                columns: carry.clone(),
+                ..InputIds::default()
+            };
+            input
+                .add_json("vendors.json", contents, "id", &carry)
+                .unwrap();
+            assert_eq!(input.ids, ["FR1", "FR2"]);
+            assert_eq!(
+                input.carried("FR1"),
+                [("owner".to_string(), "Ana".to_string())]
+            );
+            assert_eq!(input.carried("FR2"), [("owner".to_string(), String::new())]);
+        }
+    }
+
+    #[test]
+    fn json_pointers_and_numeric_ids() {
+        let mut input = InputIds::default();
+        input
+            .add_json(
+                "vendors.json",
+                r#"[{"fedramp": {"id": "FR1"}}, {"fedramp": {"id": 12345}}, {"fedramp": null}]"#,
+                "/fedramp/id",
+                &[],
+            )
+            .unwrap();
+        assert_eq!(input.ids, ["FR1", "12345"]);
+
+        assert_eq!(
+            json_field(&serde_json::json!({ "id": 7 }), "id"),
+            Some("7".to_string())
+        );
+        assert_eq!(json_field(&serde_json::json!({ "id": null }), "id"), None);
+        assert_eq!(
+            json_field(&serde_json::json!({ "id": " FR1 " }), "id"),
+            Some("FR1".to_string())
+        );
+    }
+
+    #[test]
+    fn json_column_missing_from_every_record() {
+        let err = InputIds::default()
+            .add_json("vendors.json", r#"[{"name": "Acme"}]"#, "id", &[])
+            .unwrap_err();
+        assert_eq!(err.to_string(), "no record has a value for \"id\"");
+
+        // An empty list is not an error.
+        InputIds::default()
+            .add_json("vendors.json", "[]", "id", &[])
+            .unwrap();
+    }
+
+    #[test]
+    fn carried_column_clashing_with_output() {
+        // Checked before the file is read, so it needn't exist.
+        let err = InputIds::default()
+            .add_table("missing.csv", "id", &strings(&["Status"]))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "carried column \"Status\" is also an output column"
+        );
+    }
+}
@@ -0,0 +1,875 @@
+// Copyright 2025 Maya Kaczorowski
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::error::{ErrorKind, ScrapeError};
+use crate::product::{
+    AgencyAuthorization, AuthorizationDetails, CSV_HEADER, Product, ProductOverview, Redirect,
+    ScrapeRecord,
+};
+use chrono::{SecondsFormat, Utc};
+use clap::ValueEnum;
+use csv::Writer;
+use rusqlite::types::Value;
+use rusqlite::{Connection, OptionalExtension};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Comma-separated values with one row per product
+    Csv,
+    /// A single JSON array of product objects
+    Json,
+    /// One JSON object per line (JSON Lines)
+    Jsonl,
+    /// A SQLite database keeping the latest result per product and every run's history
+    Sqlite,
+}
+
+impl Format {
+    /// Guesses the format of an existing output from its extension, falling
+    /// back to the first non-blank character of the file.
+    pub fn detect(path: &str) -> Result<Format, Box<dyn Error + Send + Sync>> {
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("csv") => return Ok(Format::Csv),
+            Some("json") => return Ok(Format::Json),
+            Some("jsonl" | "ndjson") => return Ok(Format::Jsonl),
+            Some("db" | "sqlite" | "sqlite3") => return Ok(Format::Sqlite),
+            _ => {}
+        }
+
+        let contents = fs::read(path)?;
+        if contents.starts_with(b"SQLite format 3\0") {
+            return Ok(Format::Sqlite);
+        }
+        Ok(match contents.iter().find(|b| !b.is_ascii_whitespace()) {
+            Some(b'[') => Format::Json,
+            Some(b'{') => Format::Jsonl,
+            _ => Format::Csv,
+        })
+    }
+}
+
+/// Destination for scraped rows. Implementations flush after every row so an
+/// interrupted run still leaves the rows scraped so far on disk.
+pub trait RecordWriter {
+    fn write(&mut self, record: &ScrapeRecord) -> Result<(), Box<dyn Error + Send + Sync>>;
+    fn finish(&mut self) -> Result<(), Box<dyn Error + Send + Sync>>;
+}
+
+/// The CSV header, followed by any input columns carried through.
+fn csv_header(carried: &[String]) -> Vec<&str> {
+    CSV_HEADER
+        .iter()
+        .copied()
+        .chain(carried.iter().map(String::as_str))
+        .collect()
+}
+
+/// Opens the output for a new run. File formats are truncated; a SQLite
+/// database is opened in place so earlier runs are kept. `carried` names the
+/// input columns each record brings along.
+pub fn create(
+    format: Format,
+    path: &str,
+    carried: &[String],
+) -> Result<Box<dyn RecordWriter>, Box<dyn Error + Send + Sync>> {
+    Ok(match format {
+        Format::Csv => {
+            let mut wtr = Writer::from_writer(File::create(path)?);
+            wtr.write_record(csv_header(carried))?;
+            wtr.flush()?;
+            Box::new(CsvOutput { wtr })
+        }
+        Format::Json => Box::new(JsonOutput {
+            out: BufWriter::new(File::create(path)?),
+            first: true,
+        }),
+        Format::Jsonl => Box::new(JsonLinesOutput {
+            out: BufWriter::new(File::create(path)?),
+        }),
+        Format::Sqlite => Box::new(SqliteOutput::open(path, false, carried)?),
+    })
+}
+
+/// IDs already present in an output being resumed.
+#[derive(Default)]
+pub struct ResumeState {
+    pub succeeded: HashSet<String>,
+    pub failed: HashSet<String>,
+}
+
+/// Reopens an existing output so a run can continue where it stopped. Rows
+/// for products that were scraped successfully are kept; error rows are
+/// dropped so the retried result replaces them instead of sitting alongside.
+/// For SQLite the most recent run is continued if it never finished. A
+/// missing file starts fresh.
+pub fn resume(
+    format: Format,
+    path: &str,
+    carried: &[String],
+) -> Result<(Box<dyn RecordWriter>, ResumeState), Box<dyn Error + Send + Sync>> {
+    if !Path::new(path).exists() {
+        return Ok((create(format, path, carried)?, ResumeState::default()));
+    }
+
+    let detected = Format::detect(path)?;
+    if detected != format {
+        return Err(format!(
+            "{} looks like {:?} output but --format is {:?}",
+            path, detected, format
+        )
+        .into());
+    }
+
+    let mut state = ResumeState::default();
+    let mut track = |id: &str, failed: bool| {
+        if failed {
+            state.failed.insert(id.to_string());
+        } else {
+            state.succeeded.insert(id.to_string());
+        }
+        !failed
+    };
+
+    let writer: Box<dyn RecordWriter> = match format {
+        Format::Csv => {
+            let header = csv_header(carried);
+            let mut rdr = csv::Reader::from_path(path)?;
+            if rdr.headers()?.iter().ne(header.iter().copied()) {
+                return Err(format!(
+                    "{} was written with different columns and cannot be resumed",
+                    path
+                )
+                .into());
+            }
+            let status_column = CSV_HEADER
+                .iter()
+                .position(|h| *h == "Scrape Status")
+                .unwrap();
+            let mut kept = Vec::new();
+            for record in rdr.records() {
+                let record = record?;
+                let failed = record.get(status_column) == Some("error");
+                if track(&record[0], failed) {
+                    kept.push(record);
+                }
+            }
+
+            let mut output = CsvOutput {
+                wtr: Writer::from_writer(File::create(path)?),
+            };
+            output.wtr.write_record(&header)?;
+            for record in &kept {
+                output.wtr.write_record(record)?;
+            }
+            output.wtr.flush()?;
+            Box::new(output)
+        }
+        Format::Json | Format::Jsonl => {
+            let contents = fs::read_to_string(path)?;
+            let records: Vec<serde_json::Value> = if format == Format::Json {
+                parse_json_array(&contents)?
+            } else {
+                contents
+                    .lines()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(serde_json::from_str)
+                    .collect::<Result<_, _>>()?
+            };
+            let kept: Vec<&serde_json::Value> = records
+                .iter()
+                .filter(|record| {
+                    let id = record["id"].as_str().unwrap_or_default();
+                    let failed = !record["error_message"].is_null() || !record["error"].is_null();
+                    track(id, failed)
+                })
+                .collect();
+
+            let out = BufWriter::new(File::create(path)?);
+            let mut output: Box<dyn JsonRecordWriter> = if format == Format::Json {
+                Box::new(JsonOutput { out, first: true })
+            } else {
+                Box::new(JsonLinesOutput { out })
+            };
+            for record in kept {
+                output.write_value(record)?;
+            }
+            output.into_record_writer()
+        }
+        Format::Sqlite => {
+            let output = SqliteOutput::open(path, true, carried)?;
+            state = output.run_state()?;
+            Box::new(output)
+        }
+    };
+
+    Ok((writer, state))
+}
+
+/// The JSON shape of a row. Error rows keep every product field present as
+/// `null` so consumers see the same keys on every object.
+#[derive(Serialize)]
+struct JsonRecord<'a> {
+    id: &'a str,
+    attempts: u32,
+    status: &'static str,
+    error_kind: Option<ErrorKind>,
+    error_message: Option<&'a str>,
+    final_id: Option<&'a str>,
+    final_url: Option<&'a str>,
+    overview: Option<&'a ProductOverview>,
+    authorization: Option<&'a AuthorizationDetails>,
+    agencies: Option<&'a [AgencyAuthorization]>,
+    extras: Option<&'a BTreeMap<String, String>>,
+    #[serde(skip_serializing_if = "serde_json::Map::is_empty")]
+    input: serde_json::Map<String, serde_json::Value>,
+}
+
+impl<'a> From<&'a ScrapeRecord> for JsonRecord<'a> {
+    fn from(record: &'a ScrapeRecord) -> Self {
+        let product = record.result.as_ref().ok();
+        let redirect = product.and_then(|p| p.redirect.as_ref());
+        JsonRecord {
+            id: &record.id,
+            attempts: record.attempts,
+            status: record.status(),
+            error_kind: record.result.as_ref().err().map(|e| e.kind),
+            error_message: record.result.as_ref().err().map(|e| e.message.as_str()),
+            final_id: redirect.map(|r| r.id.as_str()),
+            final_url: redirect.map(|r| r.url.as_str()),
+            overview: product.map(|p| &p.overview),
+            authorization: product.map(|p| &p.authorization),
+            agencies: product.map(|p| p.agencies.as_slice()),
+            extras: product.map(|p| &p.extras),
+            input: record
+                .input
+                .iter()
+                .map(|(column, value)| (column.clone(), value.clone().into()))
+                .collect(),
+        }
+    }
+}
+
+/// Owned form of `JsonRecord`, for reading JSON output back in.
+#[derive(Deserialize)]
+struct StoredRecord {
+    id: String,
+    #[serde(default)]
+    attempts: u32,
+    error_kind: Option<ErrorKind>,
+    /// Called `error` before error kinds were recorded.
+    #[serde(alias = "error")]
+    error_message: Option<String>,
+    final_id: Option<String>,
+    final_url: Option<String>,
+    overview: Option<ProductOverview>,
+    authorization: Option<AuthorizationDetails>,
+    agencies: Option<Vec<AgencyAuthorization>>,
+    extras: Option<BTreeMap<String, String>>,
+}
+
+impl StoredRecord {
+    fn csv_record(self) -> Vec<String> {
+        let result = match self.error_message {
+            Some(message) => Err(ScrapeError::new(
+                self.error_kind.unwrap_or(ErrorKind::Other),
+                message,
+            )),
+            None => Ok(Product {
+                id: self.id.clone(),
+                overview: self.overview.unwrap_or_default(),
+                authorization: self.authorization.unwrap_or_default(),
+                agencies: self.agencies.unwrap_or_default(),
+                extras: self.extras.unwrap_or_default(),
+                redirect: self
+                    .final_id
+                    .zip(self.final_url)
+                    .map(|(id, url)| Redirect { id, url }),
+            }),
+        };
+        ScrapeRecord {
+            id: self.id,
+            attempts: self.attempts,
+            result,
+            input: Vec::new(),
+        }
+        .csv_record()
+    }
+}
+
+/// Parses a JSON array output, including one left without its closing
+/// bracket by an interrupted run.
+fn parse_json_array<T: DeserializeOwned>(contents: &str) -> serde_json::Result<Vec<T>> {
+    let contents = contents.trim_end();
+    if contents.ends_with(']') {
+        serde_json::from_str(contents)
+    } else {
+        serde_json::from_str(&format!("{}\n]", contents))
+    }
+}
+
+/// An earlier output flattened to CSV columns, whatever format it was written
+/// in. The first column is always the product ID.
+pub struct Table {
+    pub header: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+/// Reads a CSV, JSON or JSON Lines file written by this tool.
+pub fn read_table(path: &str) -> Result<Table, Box<dyn Error + Send + Sync>> {
+    let records: Vec<StoredRecord> = match Format::detect(path)? {
+        Format::Csv => {
+            let mut rdr = csv::Reader::from_path(path)?;
+            let header: Vec<String> = rdr.headers()?.iter().map(String::from).collect();
+            if header.first().map(String::as_str) != Some("ID") {
+                return Err(format!("{} does not look like output from this tool", path).into());
+            }
+            let rows = rdr
+                .records()
+                .map(|r| r.map(|r| r.iter().map(String::from).collect()))
+                .collect::<Result<_, _>>()?;
+            return Ok(Table { header, rows });
+        }
+        Format::Json => parse_json_array(&fs::read_to_string(path)?)?,
+        Format::Jsonl => fs::read_to_string(path)?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?,
+        Format::Sqlite => return Err("reading SQLite output is not supported".into()),
+    };
+
+    Ok(Table {
+        header: CSV_HEADER.iter().map(|h| h.to_string()).collect(),
+        rows: records.into_iter().map(StoredRecord::csv_record).collect(),
+    })
+}
+
+struct CsvOutput {
+    wtr: Writer<File>,
+}
+
+impl RecordWriter for CsvOutput {
+    fn write(&mut self, record: &ScrapeRecord) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.wtr.write_record(record.csv_record())?;
+        self.wtr.flush()?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.wtr.flush()?;
+        Ok(())
+    }
+}
+
+struct JsonOutput {
+    out: BufWriter<File>,
+    first: bool,
+}
+
+/// Lets `resume` replay kept JSON rows verbatim before handing the writer back.
+trait JsonRecordWriter {
+    fn write_value(
+        &mut self,
+        record: &serde_json::Value,
+    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+    fn into_record_writer(self: Box<Self>) -> Box<dyn RecordWriter>;
+}
+
+impl JsonOutput {
+    fn write_json<T: Serialize>(&mut self, record: &T) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.out
+            .write_all(if self.first { b"[\n" } else { b",\n" })?;
+        self.first = false;
+        serde_json::to_writer_pretty(&mut self.out, record)?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+impl RecordWriter for JsonOutput {
+    fn write(&mut self, record: &ScrapeRecord) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.write_json(&JsonRecord::from(record))
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.out
+            .write_all(if self.first { b"[]\n" } else { b"\n]\n" })?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+struct JsonLinesOutput {
+    out: BufWriter<File>,
+}
+
+impl JsonLinesOutput {
+    fn write_json<T: Serialize>(&mut self, record: &T) -> Result<(), Box<dyn Error + Send + Sync>> {
+        serde_json::to_writer(&mut self.out, record)?;
+        self.out.write_all(b"\n")?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+impl RecordWriter for JsonLinesOutput {
+    fn write(&mut self, record: &ScrapeRecord) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.write_json(&JsonRecord::from(record))
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+impl JsonRecordWriter for JsonOutput {
+    fn write_value(
+        &mut self,
+        record: &serde_json::Value,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.write_json(record)
+    }
+
+    fn into_record_writer(self: Box<Self>) -> Box<dyn RecordWriter> {
+        self
+    }
+}
+
+impl JsonRecordWriter for JsonLinesOutput {
+    fn write_value(
+        &mut self,
+        record: &serde_json::Value,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.write_json(record)
+    }
+
+    fn into_record_writer(self: Box<Self>) -> Box<dyn RecordWriter> {
+        self
+    }
+}
+
+fn now() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Turns a CSV header such as "FedRAMP Ready (Raw)" into a SQL column name
+/// such as `fedramp_ready_raw`.
+fn column_name(header: &str) -> String {
+    let mut name = String::new();
+    for c in header.chars() {
+        if c.is_ascii_alphanumeric() {
+            name.push(c.to_ascii_lowercase());
+        } else if !name.is_empty() && !name.ends_with('_') {
+            name.push('_');
+        }
+    }
+    name.trim_end_matches('_').to_string()
+}
+
+/// Stores each run in `runs`, every scraped row in `history`, and the most
+/// recent successful scrape of each product in `latest`. Data columns follow
+/// the CSV header (snake_cased) and are added to existing databases as the
+/// header grows; carried input columns are prefixed with `input_`, and
+/// agencies are stored as a JSON array.
+struct SqliteOutput {
+    conn: Connection,
+    run_id: i64,
+    columns: Vec<String>,
+}
+
+impl SqliteOutput {
+    /// Opens the database and starts a new run, or with `resume` continues
+    /// the most recent run if it was interrupted before finishing.
+    fn open(
+        path: &str,
+        resume: bool,
+        carried: &[String],
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS runs (
+                run_id INTEGER PRIMARY KEY AUTOINCREMENT,
+                started_at TEXT NOT NULL,
+                finished_at TEXT
+            );
+            CREATE TABLE IF NOT EXISTS latest (
+                id TEXT PRIMARY KEY,
+                run_id INTEGER NOT NULL REFERENCES runs(run_id),
+                scraped_at TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS history (
+                run_id INTEGER NOT NULL REFERENCES runs(run_id),
+                id TEXT NOT NULL,
+                scraped_at TEXT NOT NULL,
+                error TEXT,
+                PRIMARY KEY (run_id, id)
+            );",
+        )?;
+
+        let mut columns: Vec<String> = CSV_HEADER[1..].iter().map(|h| column_name(h)).collect();
+        columns.extend(carried.iter().map(|c| column_name(&format!("input {}", c))));
+        columns.push("agencies".to_string());
+        for table in ["latest", "history"] {
+            add_missing_columns(&conn, table, &columns)?;
+        }
+
+        // Only a run that never finished is continued; resuming after a
+        // completed run starts a new one so its history stays as it was.
+        let unfinished: Option<i64> = conn
+            .query_row(
+                "SELECT run_id FROM runs WHERE run_id = (SELECT MAX(run_id) FROM runs)
+                AND finished_at IS NULL",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let run_id = match unfinished {
+            Some(run_id) if resume => run_id,
+            _ => {
+                conn.execute("INSERT INTO runs (started_at) VALUES (?1)", [now()])?;
+                conn.last_insert_rowid()
+            }
+        };
+
+        Ok(SqliteOutput {
+            conn,
+            run_id,
+            columns,
+        })
+    }
+
+    fn run_state(&self) -> rusqlite::Result<ResumeState> {
+        let mut state = ResumeState::default();
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, error IS NOT NULL FROM history WHERE run_id = ?1")?;
+        let rows = stmt.query_map([self.run_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?))
+        })?;
+        for row in rows {
+            let (id, failed) = row?;
+            if failed {
+                state.failed.insert(id);
+            } else {
+                state.succeeded.insert(id);
+            }
+        }
+        Ok(state)
+    }
+
+    fn insert(&self, table: &str, values: Vec<Value>, upsert: bool) -> rusqlite::Result<()> {
+        let (names, placeholders) = self.statement_columns(table);
+        let mut sql = format!(
+            "INSERT OR REPLACE INTO {} ({}) VALUES ({})",
+            table,
+            names.join(", "),
+            placeholders
+        );
+        if upsert {
+            sql = format!(
+                "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT(id) DO UPDATE SET {}",
+                table,
+                names.join(", "),
+                placeholders,
+                names[1..]
+                    .iter()
+                    .map(|n| format!("{0} = excluded.{0}", n))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        }
+        self.conn
+            .execute(&sql, rusqlite::params_from_iter(values))?;
+        Ok(())
+    }
+
+    /// Column list and placeholders for `insert`: `id, run_id, scraped_at`,
+    /// then `error` for history, then the data columns.
+    fn statement_columns(&self, table: &str) -> (Vec<String>, String) {
+        let mut names: Vec<String> = ["id", "run_id", "scraped_at"].map(String::from).to_vec();
+        if table == "history" {
+            names.push("error".to_string());
+        }
+        names.extend(self.columns.iter().map(|c| format!("\"{}\"", c)));
+        let placeholders = (1..=names.len())
+            .map(|i| format!("?{}", i))
+            .collect::<Vec<_>>()
+            .join(", ");
+        (names, placeholders)
+    }
+}
+
+fn add_missing_columns(conn: &Connection, table: &str, columns: &[String]) -> rusqlite::Result<()> {
+    let existing: HashSet<String> = conn
+        .prepare(&format!("PRAGMA table_info({})", table))?
+        .query_map([], |row| row.get::<_, String>(1))?
+        .collect::<rusqlite::Result<_>>()?;
+
+    for column in columns.iter().filter(|c| !existing.contains(*c)) {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN \"{}\" TEXT", table, column),
+            [],
+        )?;
+    }
+    Ok(())
+}
+
+fn text_or_null(value: String) -> Value {
+    if value.is_empty() {
+        Value::Null
+    } else {
+        Value::Text(value)
+    }
+}
+
+impl RecordWriter for SqliteOutput {
+    fn write(&mut self, record: &ScrapeRecord) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut data: Vec<Value> = record
+            .csv_record()
+            .into_iter()
+            .skip(1)
+            .map(text_or_null)
+            .collect();
+        let agencies = match &record.result {
+            Ok(product) => Value::Text(serde_json::to_string(&product.agencies)?),
+            Err(_) => Value::Null,
+        };
+        data.push(agencies);
+
+        // Kept alongside error_message because run_state and earlier
+        // databases rely on it.
+        let error = match &record.result {
+            Ok(_) => Value::Null,
+            Err(e) => Value::Text(e.message.clone()),
+        };
+
+        let head = [
+            Value::Text(record.id.clone()),
+            Value::Integer(self.run_id),
+            Value::Text(now()),
+        ];
+        let history = head
+            .iter()
+            .cloned()
+            .chain([error])
+            .chain(data.iter().cloned())
+            .collect();
+        self.insert("history", history, false)?;
+
+        if record.result.is_ok() {
+            let latest = head.into_iter().chain(data).collect();
+            self.insert("latest", latest, true)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.conn.execute(
+            "UPDATE runs SET finished_at = ?1 WHERE run_id = ?2",
+            rusqlite::params![now(), self.run_id],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// A path in the temp directory unique to this test, removed first.
+    fn temp_path(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("fedramp-scraper-{}-{}", std::process::id(), name));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    fn record(id: &str, ok: bool) -> ScrapeRecord {
+        let result = if ok {
+            Ok(Product {
+                id: id.to_string(),
+                overview: ProductOverview {
+                    provider_name: Some(format!("Provider {}", id)),
+                    ..Default::default()
+                },
+                authorization: AuthorizationDetails::default(),
+                agencies: Vec::new(),
+                extras: BTreeMap::new(),
+                redirect: None,
+            })
+        } else {
+            Err(ScrapeError::new(ErrorKind::Timeout, "timed out"))
+        };
+        ScrapeRecord {
+            id: id.to_string(),
+            attempts: 1,
+            result,
+            input: Vec::new(),
+        }
+    }
+
+    /// Writes A and B successfully and C as an error, without finishing, as
+    /// an interrupted run would.
+    fn interrupted_output(format: Format, path: &str) {
+        let mut output = create(format, path, &[]).unwrap();
+        for (id, ok) in [("A", true), ("B", true), ("C", false)] {
+            output.write(&record(id, ok)).unwrap();
+        }
+    }
+
+    fn ids(set: &HashSet<String>) -> Vec<&str> {
+        let mut ids: Vec<&str> = set.iter().map(String::as_str).collect();
+        ids.sort();
+        ids
+    }
+
+    /// Resumes the interrupted output, retries C successfully and checks the
+    /// rows that end up in the file.
+    fn check_resume(format: Format, name: &str) {
+        let path = temp_path(name);
+        let path = path.to_str().unwrap();
+        interrupted_output(format, path);
+
+        let (mut output, state) = resume(format, path, &[]).unwrap();
+        assert_eq!(ids(&state.succeeded), ["A", "B"]);
+        assert_eq!(ids(&state.failed), ["C"]);
+
+        let table = read_table(path).unwrap();
+        let kept: Vec<&str> = table.rows.iter().map(|r| r[0].as_str()).collect();
+        assert_eq!(kept, ["A", "B"]);
+
+        output.write(&record("C", true)).unwrap();
+        output.finish().unwrap();
+        drop(output);
+
+        let table = read_table(path).unwrap();
+        let status = table
+            .header
+            .iter()
+            .position(|h| h == "Scrape Status")
+            .unwrap();
+        let rows: Vec<(&str, &str)> = table
+            .rows
+            .iter()
+            .map(|r| (r[0].as_str(), r[status].as_str()))
+            .collect();
+        assert_eq!(rows, [("A", "ok"), ("B", "ok"), ("C", "ok")]);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn csv_resume() {
+        check_resume(Format::Csv, "resume.csv");
+    }
+
+    #[test]
+    fn json_resume_without_closing_bracket() {
+        let path = temp_path("unclosed.json");
+        interrupted_output(Format::Json, path.to_str().unwrap());
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(!contents.trim_end().ends_with(']'));
+        fs::remove_file(&path).unwrap();
+
+        check_resume(Format::Json, "resume.json");
+    }
+
+    #[test]
+    fn jsonl_resume() {
+        check_resume(Format::Jsonl, "resume.jsonl");
+    }
+
+    #[test]
+    fn resume_rejects_different_columns() {
+        let path = temp_path("columns.csv");
+        let path = path.to_str().unwrap();
+        interrupted_output(Format::Csv, path);
+        let before = fs::read_to_string(path).unwrap();
+
+        let err = resume(Format::Csv, path, &["Owner".to_string()])
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("different columns"), "{}", err);
+        // The file is left as it was.
+        assert_eq!(fs::read_to_string(path).unwrap(), before);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn resume_rejects_a_different_format() {
+        for (format, name) in [
+            (Format::Json, "format.jsonl"),
+            (Format::Jsonl, "format.json"),
+        ] {
+            let path = temp_path(name);
+            let path = path.to_str().unwrap();
+            let written = if format == Format::Json {
+                Format::Jsonl
+            } else {
+                Format::Json
+            };
+            interrupted_output(written, path);
+
+            let err = resume(format, path, &[]).err().unwrap();
+            assert!(err.to_string().contains("looks like"), "{}", err);
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn sqlite_resume_continues_only_unfinished_runs() {
+        let path = temp_path("resume.db");
+        let path = path.to_str().unwrap();
+
+        let mut first = SqliteOutput::open(path, false, &[]).unwrap();
+        first.finish().unwrap();
+        let first_run = first.run_id;
+        drop(first);
+
+        // The last run finished, so resuming starts a new one.
+        let second = SqliteOutput::open(path, true, &[]).unwrap();
+        assert_ne!(second.run_id, first_run);
+        let second_run = second.run_id;
+        drop(second);
+
+        // That one was interrupted, so it is continued.
+        let mut third = SqliteOutput::open(path, true, &[]).unwrap();
+        assert_eq!(third.run_id, second_run);
+        third.finish().unwrap();
+
+        let finished: Option<String> = third
+            .conn
+            .query_row(
+                "SELECT finished_at FROM runs WHERE run_id = ?1",
+                [first_run],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!(finished.is_some());
+        drop(third);
+        fs::remove_file(path).unwrap();
+    }
+}
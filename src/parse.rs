@@ -0,0 +1,526 @@
+// Copyright 2025 Maya Kaczorowski
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Text-level parsing of product pages. The HTML parser, which reads pages
+//! from the browser and from disk alike, hands the text of each block to
+//! these functions.
+use crate::dates::parse_date;
+use crate::fields::{self, FieldMap, FieldSpec, FieldType, ParsedFields};
+use crate::product::{AgencyAuthorization, AgencyRole};
+
+/// Text read from a product page: the blocks under each section heading the
+/// field map names, every block on the page, and the page's `<h1>`. Line
+/// breaks inside a block are kept as `\n`.
+#[derive(Debug, Default)]
+pub struct PageText {
+    pub sections: Vec<(String, Vec<String>)>,
+    pub blocks: Vec<String>,
+    pub heading: Option<String>,
+}
+
+fn is_colon(c: char) -> bool {
+    matches!(c, ':' | '：')
+}
+
+/// `text[at..]` starts with `word`, ignoring ASCII case.
+fn starts_with_word(text: &str, at: usize, word: &str) -> bool {
+    text.get(at..at + word.len())
+        .is_some_and(|s| s.eq_ignore_ascii_case(word))
+}
+
+/// A label found in a block: where it starts and ends (including any colon
+/// after it), and whether it had a colon.
+#[derive(Debug, Clone, Copy)]
+struct LabelMatch {
+    spec: usize,
+    start: usize,
+    end: usize,
+    colon: bool,
+}
+
+/// Every place `label` appears in `text` as whole words, ignoring case and
+/// treating any run of whitespace between words as a single space. Whitespace
+/// before the colon is allowed.
+fn find_label(text: &str, label: &str, spec: usize) -> Vec<LabelMatch> {
+    let words: Vec<&str> = label.split_whitespace().collect();
+    let Some(first) = words.first() else {
+        return Vec::new();
+    };
+
+    let mut matches = Vec::new();
+    for (start, _) in text.char_indices() {
+        let at_boundary = text[..start]
+            .chars()
+            .next_back()
+            .is_none_or(|c| !c.is_alphanumeric());
+        if !at_boundary || !starts_with_word(text, start, first) {
+            continue;
+        }
+
+        let mut pos = start + first.len();
+        let mut matched = true;
+        for word in &words[1..] {
+            let rest = &text[pos..];
+            let skipped = rest.len() - rest.trim_start().len();
+            if skipped == 0 || !starts_with_word(text, pos + skipped, word) {
+                matched = false;
+                break;
+            }
+            pos += skipped + word.len();
+        }
+        if !matched || text[pos..].starts_with(|c: char| c.is_alphanumeric()) {
+            continue;
+        }
+
+        let rest = &text[pos..];
+        let after_space = rest.trim_start_matches([' ', '\t']);
+        let colon = after_space.starts_with(is_colon);
+        let end = if colon {
+            pos + (rest.len() - after_space.len())
+                + after_space.chars().next().map_or(0, char::len_utf8)
+        } else {
+            pos
+        };
+        matches.push(LabelMatch {
+            spec,
+            start,
+            end,
+            colon,
+        });
+    }
+    matches
+}
+
+/// Whether `text[..at]` has nothing but whitespace since the last line break.
+fn starts_line(text: &str, at: usize) -> bool {
+    text[..at]
+        .rsplit('\n')
+        .next()
+        .is_none_or(|line| line.trim().is_empty())
+}
+
+/// The labels in `text`, in order and without overlaps. Where two labels
+/// overlap, the one starting first wins, then the longer one, then the one
+/// listed first in the field map. Labels need a colon unless `bare_ok` and the
+/// label is the whole block. Without `bare_ok`, where labels aren't confined
+/// to a section, a label must also start a line or follow the value of
+/// another label on its line, so "Leveraged Service Model:" or "Authorization
+/// Status:" aren't read as "Service Model:" or "Status:".
+fn labels_in(text: &str, specs: &[&FieldSpec], bare_ok: bool) -> Vec<LabelMatch> {
+    let trimmed = text.trim();
+    let mut found: Vec<LabelMatch> = specs
+        .iter()
+        .enumerate()
+        .flat_map(|(i, spec)| find_label(text, &spec.label, i))
+        .filter(|m| m.colon || (bare_ok && text[m.start..m.end].len() == trimmed.len()))
+        .collect();
+    found.sort_by_key(|m| (m.start, std::cmp::Reverse(m.end), m.spec));
+
+    let mut labels: Vec<LabelMatch> = Vec::new();
+    for m in found {
+        let anchored = bare_ok
+            || starts_line(text, m.start)
+            || labels
+                .last()
+                .is_some_and(|last| !text[last.end..m.start].contains('\n'));
+        if anchored && labels.last().is_none_or(|last| m.start >= last.end) {
+            labels.push(m);
+        }
+    }
+    labels
+}
+
+/// The first non-empty line of `text`, trimmed.
+fn first_line(text: &str) -> Option<String> {
+    text.lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(String::from)
+}
+
+/// Longest text value, in words, taken from the block after an empty label.
+const MAX_SIBLING_WORDS: usize = 10;
+
+/// Longest date value, in words, that is taken from the block after an empty
+/// label without being a recognizable date (e.g. "Not yet assessed").
+const MAX_SIBLING_DATE_WORDS: usize = 3;
+
+/// The value of `spec` if `text`, the block after its empty label, looks like
+/// one: a date for date fields, and for text fields a short line that isn't
+/// itself a "Label: value" pair. Anything else is an unrelated paragraph.
+fn sibling_value(text: &str, spec: &FieldSpec) -> Option<String> {
+    let value = first_line(text)?;
+    if unmapped_label(&value).is_some() {
+        return None;
+    }
+    let words = value.split_whitespace().count();
+    let fits = match spec.field_type() {
+        FieldType::Date => parse_date(&value).is_some() || words <= MAX_SIBLING_DATE_WORDS,
+        FieldType::Text => words <= MAX_SIBLING_WORDS,
+    };
+    fits.then_some(value)
+}
+
+/// The label of a block that looks like "Label: value", when no field is
+/// mapped to it. Short labels starting with a letter only, so sentences that
+/// happen to contain a colon are not mistaken for fields.
+fn unmapped_label(text: &str) -> Option<(String, String)> {
+    let (label, value) = text.trim().split_once(is_colon)?;
+    let label = label.trim();
+    if label.is_empty()
+        || label.len() > 60
+        || label.contains('\n')
+        || !label.starts_with(|c: char| c.is_alphabetic())
+    {
+        return None;
+    }
+    Some((label.to_string(), first_line(value)?))
+}
+
+/// Fills fields from `texts` using `specs`. A block may hold several labels;
+/// each value runs to the next label or the end of the line. A label with
+/// nothing after it takes its value from the next block, for layouts that
+/// render the value in a sibling element, if that block looks like a value.
+/// Returns the blocks that neither held a label nor supplied a value.
+fn apply_fields<'a>(
+    specs: &[&FieldSpec],
+    texts: &'a [String],
+    bare_ok: bool,
+    parsed: &mut ParsedFields,
+) -> Vec<&'a str> {
+    let labels: Vec<Vec<LabelMatch>> = texts
+        .iter()
+        .map(|text| labels_in(text, specs, bare_ok))
+        .collect();
+
+    let mut unmatched = Vec::new();
+    let mut used_as_value = false;
+    for (i, text) in texts.iter().enumerate() {
+        let in_block = &labels[i];
+        if in_block.is_empty() {
+            if !std::mem::take(&mut used_as_value) {
+                unmatched.push(text.as_str());
+            }
+            continue;
+        }
+        used_as_value = false;
+
+        for (j, m) in in_block.iter().enumerate() {
+            let value_end = in_block.get(j + 1).map_or(text.len(), |next| next.start);
+            let mut value = first_line(&text[m.end..value_end]);
+            let last = j + 1 == in_block.len();
+            if value.is_none()
+                && last
+                && let Some(next) = texts.get(i + 1)
+                && labels[i + 1].is_empty()
+            {
+                value = sibling_value(next, specs[m.spec]);
+                used_as_value = value.is_some();
+            }
+            if let Some(value) = value {
+                fields::assign(parsed, specs[m.spec], value);
+            }
+        }
+    }
+    unmatched
+}
+
+/// Builds the authorization details, overview and extras from the page text.
+/// Fields with a section are only looked for in that section; the rest
+/// anywhere on the page. Any other "Label: value" paragraph in a section goes
+/// into the extras. The offering name falls back to the page heading, which
+/// is where the marketplace shows it when there is no explicit label.
+pub fn parse_fields(map: &FieldMap, page: &PageText) -> ParsedFields {
+    let mut parsed = ParsedFields::default();
+
+    let anywhere: Vec<&FieldSpec> = map.fields.iter().filter(|f| f.section.is_none()).collect();
+    let mut unmatched = Vec::new();
+    for (heading, texts) in &page.sections {
+        let specs: Vec<&FieldSpec> = map
+            .fields
+            .iter()
+            .filter(|f| f.section.as_deref() == Some(heading.as_str()))
+            .collect();
+        unmatched.extend(apply_fields(&specs, texts, true, &mut parsed));
+    }
+
+    // A bare label anywhere on the page is too likely to be ordinary text
+    // (e.g. a "FedRAMP Ready" status badge), so colons are required here.
+    apply_fields(&anywhere, &page.blocks, false, &mut parsed);
+
+    for text in unmatched {
+        if !labels_in(text, &anywhere, false).is_empty() {
+            continue;
+        }
+        if let Some((label, value)) = unmapped_label(text) {
+            parsed.extras.entry(label).or_insert(value);
+        }
+    }
+
+    if parsed.overview.offering_name.is_none() {
+        parsed.overview.offering_name = page
+            .heading
+            .as_deref()
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .map(String::from);
+    }
+
+    parsed
+}
+
+/// Sections headed "Agencies Using this Service" (or mentioning reuse) list
+/// reusing agencies; any other agency section names the sponsor.
+pub fn agency_role(heading: &str) -> AgencyRole {
+    if heading.contains("Using") || heading.contains("Reus") {
+        AgencyRole::Reuse
+    } else {
+        AgencyRole::Sponsor
+    }
+}
+
+/// Splits an entry like "Department of Energy - 03/14/2022" or
+/// "Department of Energy (03/14/2022)" into the agency name and its date.
+fn split_agency_date(text: &str) -> (String, Option<String>) {
+    let text = text.trim();
+    let date_start = text
+        .char_indices()
+        .rev()
+        .find(|(_, c)| !(c.is_ascii_digit() || *c == '/' || *c == ')'))
+        .map_or(0, |(i, c)| i + c.len_utf8());
+    let date = text[date_start..].trim_end_matches(')');
+
+    if !date.contains('/') {
+        return (text.to_string(), None);
+    }
+
+    let agency = text[..date_start]
+        .trim_end_matches(|c: char| c.is_whitespace() || matches!(c, '-' | '–' | '(' | ':' | ','))
+        .to_string();
+    (agency, Some(date.to_string()))
+}
+
+/// Builds agency entries from the text of each item in one agency section.
+pub fn parse_agencies<'a>(
+    role: AgencyRole,
+    items: impl IntoIterator<Item = &'a str>,
+) -> Vec<AgencyAuthorization> {
+    items
+        .into_iter()
+        .map(split_agency_date)
+        .filter(|(agency, _)| !agency.is_empty())
+        .map(|(agency, date)| AgencyAuthorization { agency, role, date })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fields::AUTH_SECTION;
+
+    /// Parses `blocks` as the Authorization Details section, with the
+    /// built-in field map.
+    fn parse_auth(blocks: &[&str]) -> ParsedFields {
+        let page = PageText {
+            sections: vec![(
+                AUTH_SECTION.to_string(),
+                blocks.iter().map(|b| b.to_string()).collect(),
+            )],
+            ..PageText::default()
+        };
+        parse_fields(&FieldMap::default(), &page)
+    }
+
+    fn raw(milestone: &Option<crate::product::Milestone>) -> Option<&str> {
+        milestone.as_ref().map(|m| m.raw.as_str())
+    }
+
+    #[test]
+    fn two_labels_in_one_block() {
+        let parsed = parse_auth(&["FedRAMP Ready: 01/02/2020 PMO Review: 02/03/2020"]);
+        assert_eq!(raw(&parsed.authorization.fedramp_ready), Some("01/02/2020"));
+        assert_eq!(raw(&parsed.authorization.pmo_review), Some("02/03/2020"));
+    }
+
+    #[test]
+    fn colon_and_whitespace_variants() {
+        let parsed = parse_auth(&[
+            "fedramp   READY : 01/02/2020",
+            "PMO Review：02/03/2020",
+            "Annual\tAssessment:04/05/2023",
+        ]);
+        let auth = &parsed.authorization;
+        assert_eq!(raw(&auth.fedramp_ready), Some("01/02/2020"));
+        assert_eq!(raw(&auth.pmo_review), Some("02/03/2020"));
+        assert_eq!(raw(&auth.annual_assessment), Some("04/05/2023"));
+    }
+
+    #[test]
+    fn value_in_next_block() {
+        let parsed = parse_auth(&[
+            "Authorizing Entity Review:",
+            "06/07/2020",
+            "FedRAMP Authorized",
+            "05/06/2021",
+            "Independent Assessor",
+            "Coalfire Systems",
+        ]);
+        let auth = &parsed.authorization;
+        assert_eq!(raw(&auth.authorizing_entity_review), Some("06/07/2020"));
+        assert_eq!(raw(&auth.fedramp_authorized), Some("05/06/2021"));
+        assert_eq!(
+            auth.independent_assessor.as_deref(),
+            Some("Coalfire Systems")
+        );
+        assert!(parsed.extras.is_empty());
+    }
+
+    #[test]
+    fn empty_value_does_not_take_unrelated_paragraph() {
+        let parsed = parse_auth(&[
+            "Annual Assessment:",
+            "See the notes below for details on this authorization.",
+            "Independent Assessor:",
+            "This product was assessed under the previous baseline and is being reviewed again.",
+            "PMO Review:",
+            "Authorization Type: JAB",
+        ]);
+        let auth = &parsed.authorization;
+        assert!(auth.annual_assessment.is_none());
+        assert!(auth.independent_assessor.is_none());
+        assert!(auth.pmo_review.is_none());
+        assert_eq!(
+            parsed.extras.get("Authorization Type").map(String::as_str),
+            Some("JAB")
+        );
+    }
+
+    #[test]
+    fn value_stops_at_end_of_line() {
+        // The assessor used to swallow the rest of the block, next label
+        // included.
+        let parsed = parse_auth(&["Independent Assessor: Coalfire\nAnnual Assessment: 04/05/2023"]);
+        let auth = &parsed.authorization;
+        assert_eq!(auth.independent_assessor.as_deref(), Some("Coalfire"));
+        assert_eq!(raw(&auth.annual_assessment), Some("04/05/2023"));
+
+        let parsed = parse_auth(&["Independent Assessor: Coalfire\nsecond line"]);
+        assert_eq!(
+            parsed.authorization.independent_assessor.as_deref(),
+            Some("Coalfire")
+        );
+    }
+
+    #[test]
+    fn bare_label_only_counts_in_its_section() {
+        let page = PageText {
+            blocks: vec!["FedRAMP Ready".to_string(), "01/02/2020".to_string()],
+            ..PageText::default()
+        };
+        let parsed = parse_fields(&FieldMap::default(), &page);
+        assert!(parsed.authorization.fedramp_ready.is_none());
+    }
+
+    #[test]
+    fn label_inside_a_longer_label_is_not_matched() {
+        let page = PageText {
+            blocks: vec![
+                "Leveraged Service Model: IaaS".to_string(),
+                "Deployment Model: Public Cloud".to_string(),
+            ],
+            ..PageText::default()
+        };
+        let parsed = parse_fields(&FieldMap::default(), &page);
+        assert!(parsed.overview.service_model.is_none());
+        assert_eq!(
+            parsed.overview.deployment_model.as_deref(),
+            Some("Public Cloud")
+        );
+    }
+
+    #[test]
+    fn longer_label_in_a_section_is_kept_as_an_extra() {
+        let block = "Authorization Status: Revoked";
+        let page = PageText {
+            sections: vec![(AUTH_SECTION.to_string(), vec![block.to_string()])],
+            blocks: vec![block.to_string()],
+            ..PageText::default()
+        };
+        let parsed = parse_fields(&FieldMap::default(), &page);
+        assert!(parsed.overview.status.is_none());
+        assert_eq!(
+            parsed
+                .extras
+                .get("Authorization Status")
+                .map(String::as_str),
+            Some("Revoked")
+        );
+    }
+
+    #[test]
+    fn labels_start_a_line_or_follow_a_value() {
+        let page = PageText {
+            blocks: vec![
+                "Impact Level: Moderate Status: FedRAMP Authorized".to_string(),
+                "Overview\n  Service Model: SaaS".to_string(),
+            ],
+            ..PageText::default()
+        };
+        let parsed = parse_fields(&FieldMap::default(), &page);
+        let overview = &parsed.overview;
+        assert_eq!(overview.impact_level.as_deref(), Some("Moderate"));
+        assert_eq!(overview.status.as_deref(), Some("FedRAMP Authorized"));
+        assert_eq!(overview.service_model.as_deref(), Some("SaaS"));
+    }
+
+    #[test]
+    fn unmapped_labels() {
+        assert_eq!(
+            unmapped_label("Authorization Type: JAB P-ATO"),
+            Some(("Authorization Type".to_string(), "JAB P-ATO".to_string()))
+        );
+        assert_eq!(unmapped_label("2021: a year to remember"), None);
+        assert_eq!(unmapped_label("No colon here"), None);
+        assert_eq!(unmapped_label("Label:"), None);
+        assert_eq!(
+            unmapped_label(&format!("{}: value", "a very long sentence ".repeat(4))),
+            None
+        );
+    }
+
+    #[test]
+    fn agency_dates() {
+        assert_eq!(
+            split_agency_date("Department of Energy - 03/14/2022"),
+            (
+                "Department of Energy".to_string(),
+                Some("03/14/2022".to_string())
+            )
+        );
+        assert_eq!(
+            split_agency_date("Department of Energy (03/14/2022)"),
+            (
+                "Department of Energy".to_string(),
+                Some("03/14/2022".to_string())
+            )
+        );
+        assert_eq!(
+            split_agency_date("General Services Administration"),
+            ("General Services Administration".to_string(), None)
+        );
+        assert_eq!(
+            split_agency_date("Department of Defense 2"),
+            ("Department of Defense 2".to_string(), None)
+        );
+    }
+}
@@ -0,0 +1,89 @@
+// Copyright 2025 Maya Kaczorowski
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::error::ScrapeError;
+use crate::product::ScrapeRecord;
+use crate::retry::RetryPolicy;
+use crate::source::ProductSource;
+use std::collections::{BTreeMap, VecDeque};
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+
+/// Scrapes `ids` with one worker per source (e.g. per WebDriver session).
+/// Workers pull the next ID from a shared queue, and finished records are
+/// handed to `on_record` in input order regardless of which worker finishes
+/// first.
+pub async fn scrape_all<S, F>(
+    sources: &[S],
+    ids: &[String],
+    policy: RetryPolicy,
+    mut on_record: F,
+) -> Result<(), Box<dyn Error + Send + Sync>>
+where
+    S: ProductSource,
+    F: FnMut(ScrapeRecord) -> Result<(), Box<dyn Error + Send + Sync>>,
+{
+    let total = ids.len();
+    let queue = Arc::new(Mutex::new(
+        ids.iter().cloned().enumerate().collect::<VecDeque<_>>(),
+    ));
+    let (tx, mut rx) = mpsc::unbounded_channel();
+
+    let mut workers = JoinSet::new();
+    for source in sources {
+        let source = source.clone();
+        let queue = Arc::clone(&queue);
+        let tx = tx.clone();
+        workers.spawn(async move {
+            loop {
+                let Some((i, id)) = queue.lock().unwrap().pop_front() else {
+                    break;
+                };
+                eprintln!("[{}/{}] Processing ID: {}", i + 1, total, id);
+
+                let (result, attempts) = policy
+                    .run(&format!("ID {}", id), ScrapeError::is_retryable, || {
+                        source.fetch(&id)
+                    })
+                    .await;
+                let record = ScrapeRecord {
+                    id,
+                    attempts,
+                    result,
+                    input: Vec::new(),
+                };
+                if tx.send((i, record)).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    drop(tx);
+
+    let mut pending = BTreeMap::new();
+    let mut next = 0;
+    while let Some((i, record)) = rx.recv().await {
+        pending.insert(i, record);
+        while let Some(record) = pending.remove(&next) {
+            on_record(record)?;
+            next += 1;
+        }
+    }
+
+    while let Some(joined) = workers.join_next().await {
+        joined?;
+    }
+    Ok(())
+}
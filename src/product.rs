@@ -0,0 +1,261 @@
+// Copyright 2025 Maya Kaczorowski
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::dates::parse_date;
+use crate::error::ScrapeError;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A milestone value as shown on the page, plus the date it was parsed to.
+/// `date` is `None` when the text isn't a recognizable date.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Milestone {
+    pub raw: String,
+    pub date: Option<NaiveDate>,
+}
+
+impl Milestone {
+    pub fn parse(raw: String) -> Self {
+        let date = parse_date(&raw);
+        Milestone { raw, date }
+    }
+
+    /// The date in ISO-8601 (YYYY-MM-DD) form, or empty if it didn't parse.
+    pub fn iso(&self) -> String {
+        self.date.map(|d| d.to_string()).unwrap_or_default()
+    }
+}
+
+/// Milestones and assessor from the "Authorization Details" section.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AuthorizationDetails {
+    pub fedramp_ready: Option<Milestone>,
+    pub authorizing_entity_review: Option<Milestone>,
+    pub pmo_review: Option<Milestone>,
+    pub fedramp_authorized: Option<Milestone>,
+    pub annual_assessment: Option<Milestone>,
+    pub independent_assessor: Option<String>,
+}
+
+impl AuthorizationDetails {
+    /// Milestones paired with their column names, in output order.
+    pub fn milestones(&self) -> [(&'static str, Option<&Milestone>); 5] {
+        [
+            ("FedRAMP Ready", self.fedramp_ready.as_ref()),
+            (
+                "Authorizing Entity Review",
+                self.authorizing_entity_review.as_ref(),
+            ),
+            ("PMO Review", self.pmo_review.as_ref()),
+            ("FedRAMP Authorized", self.fedramp_authorized.as_ref()),
+            ("Annual Assessment", self.annual_assessment.as_ref()),
+        ]
+    }
+
+    /// Milestones that had a value on the page but could not be parsed as a date.
+    pub fn unparsed_dates(&self) -> Vec<(&'static str, &str)> {
+        self.milestones()
+            .into_iter()
+            .filter_map(|(label, m)| {
+                m.filter(|m| m.date.is_none())
+                    .map(|m| (label, m.raw.as_str()))
+            })
+            .collect()
+    }
+}
+
+/// What the product is and who offers it, from the top of the product page.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ProductOverview {
+    pub provider_name: Option<String>,
+    pub offering_name: Option<String>,
+    pub service_model: Option<String>,
+    pub deployment_model: Option<String>,
+    pub impact_level: Option<String>,
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgencyRole {
+    /// The agency that sponsored the original authorization.
+    Sponsor,
+    /// An agency that has reused the existing ATO.
+    Reuse,
+}
+
+impl AgencyRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AgencyRole::Sponsor => "Sponsor",
+            AgencyRole::Reuse => "Reuse",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AgencyAuthorization {
+    pub agency: String,
+    pub role: AgencyRole,
+    pub date: Option<String>,
+}
+
+/// Where the marketplace sent us when the requested ID led to a different
+/// product.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Redirect {
+    pub id: String,
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Product {
+    pub id: String,
+    pub overview: ProductOverview,
+    pub authorization: AuthorizationDetails,
+    pub agencies: Vec<AgencyAuthorization>,
+    /// "Label: value" pairs the field map doesn't assign to a built-in
+    /// column, keyed by label (or by the configured column name).
+    #[serde(default)]
+    pub extras: BTreeMap<String, String>,
+    /// Set when the requested ID redirected to another product; the fields
+    /// above are then that product's.
+    #[serde(default)]
+    pub redirect: Option<Redirect>,
+}
+
+/// One output row: the product scraped for an ID, or the error that stopped it,
+/// along with how many attempts it took.
+#[derive(Debug)]
+pub struct ScrapeRecord {
+    pub id: String,
+    pub attempts: u32,
+    pub result: Result<Product, ScrapeError>,
+    /// Input columns carried through to the output, as `(column, value)`.
+    pub input: Vec<(String, String)>,
+}
+
+pub static CSV_HEADER: [&str; 24] = [
+    "ID",
+    "FedRAMP Ready",
+    "FedRAMP Ready (Raw)",
+    "Authorizing Entity Review",
+    "Authorizing Entity Review (Raw)",
+    "PMO Review",
+    "PMO Review (Raw)",
+    "FedRAMP Authorized",
+    "FedRAMP Authorized (Raw)",
+    "Annual Assessment",
+    "Annual Assessment (Raw)",
+    "Independent Assessor",
+    "Cloud Service Provider",
+    "Cloud Service Offering",
+    "Service Model",
+    "Deployment Model",
+    "Impact Level",
+    "Status",
+    "Attempts",
+    "Scrape Status",
+    "Error Kind",
+    "Error Message",
+    "Final ID",
+    "Final URL",
+];
+
+/// Columns filled from the product itself: everything up to "Attempts".
+const PRODUCT_COLUMNS: usize = 18;
+
+impl Product {
+    pub fn csv_record(&self) -> Vec<String> {
+        let auth = &self.authorization;
+        let overview = &self.overview;
+
+        let mut record = vec![self.id.clone()];
+        for (_, milestone) in auth.milestones() {
+            record.push(milestone.map(Milestone::iso).unwrap_or_default());
+            record.push(milestone.map(|m| m.raw.clone()).unwrap_or_default());
+        }
+        record.extend(
+            [
+                &auth.independent_assessor,
+                &overview.provider_name,
+                &overview.offering_name,
+                &overview.service_model,
+                &overview.deployment_model,
+                &overview.impact_level,
+                &overview.status,
+            ]
+            .into_iter()
+            .map(|value| value.clone().unwrap_or_default()),
+        );
+        record
+    }
+}
+
+pub static AGENCY_CSV_HEADER: [&str; 4] = ["ID", "Agency", "Role", "Date"];
+
+impl Product {
+    /// One row per agency, keyed by product ID, for the agencies CSV.
+    pub fn agency_csv_records(&self) -> Vec<[&str; 4]> {
+        self.agencies
+            .iter()
+            .map(|a| {
+                [
+                    self.id.as_str(),
+                    a.agency.as_str(),
+                    a.role.as_str(),
+                    a.date.as_deref().unwrap_or_default(),
+                ]
+            })
+            .collect()
+    }
+}
+
+impl ScrapeRecord {
+    /// "ok", "redirected" or "error", for the Scrape Status column.
+    pub fn status(&self) -> &'static str {
+        match &self.result {
+            Ok(product) if product.redirect.is_some() => "redirected",
+            Ok(_) => "ok",
+            Err(_) => "error",
+        }
+    }
+
+    /// The CSV row for this record. Error rows leave the product columns
+    /// blank and describe the failure in the error columns; redirected rows
+    /// name the product they ended up at. Carried input columns come last.
+    pub fn csv_record(&self) -> Vec<String> {
+        let mut record = match &self.result {
+            Ok(product) => product.csv_record(),
+            Err(_) => {
+                let mut record = vec![String::new(); PRODUCT_COLUMNS];
+                record[0] = self.id.clone();
+                record
+            }
+        };
+        record.push(self.attempts.to_string());
+        record.push(self.status().to_string());
+        match &self.result {
+            Ok(_) => record.extend([String::new(), String::new()]),
+            Err(e) => record.extend([e.kind.as_str().to_string(), e.message.clone()]),
+        }
+        match self.result.as_ref().ok().and_then(|p| p.redirect.as_ref()) {
+            Some(redirect) => record.extend([redirect.id.clone(), redirect.url.clone()]),
+            None => record.extend([String::new(), String::new()]),
+        }
+        record.extend(self.input.iter().map(|(_, value)| value.clone()));
+        record
+    }
+}
@@ -0,0 +1,74 @@
+// Copyright 2025 Maya Kaczorowski
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use rand::Rng;
+use std::fmt::Display;
+use std::future::Future;
+use std::time::Duration;
+
+/// How many times to retry a failed product and how long to wait in between.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `retry` (starting at 1): the base delay
+    /// doubled for each earlier retry and capped at `max_delay`, with the upper
+    /// half randomized so parallel failures don't retry in lockstep.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let exp = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+            .min(self.max_delay);
+        let half = exp / 2;
+        half + half.mul_f64(rand::rng().random::<f64>())
+    }
+
+    /// Runs `attempt` until it succeeds, fails with an error `retryable`
+    /// rejects, or the retries are used up. Returns the last result together
+    /// with the number of attempts made.
+    pub async fn run<T, E, R, F, Fut>(
+        &self,
+        label: &str,
+        retryable: R,
+        mut attempt: F,
+    ) -> (Result<T, E>, u32)
+    where
+        E: Display,
+        R: Fn(&E) -> bool,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            match attempt().await {
+                Err(e) if attempts <= self.retries && retryable(&e) => {
+                    let delay = self.delay(attempts);
+                    eprintln!(
+                        "Attempt {} for {} failed: {}; retrying in {:.1}s",
+                        attempts,
+                        label,
+                        e,
+                        delay.as_secs_f64()
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                result => return (result, attempts),
+            }
+        }
+    }
+}
@@ -0,0 +1,243 @@
+// Copyright 2025 Maya Kaczorowski
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::archive::Archive;
+use crate::discover;
+use crate::error::{ErrorKind, ScrapeError};
+use crate::fields::FieldMap;
+use crate::html;
+use crate::product::{Product, Redirect};
+use std::error::Error;
+use std::time::Duration;
+use thirtyfour::prelude::*;
+
+static URL_BASE: &str = "https://marketplace.fedramp.gov/products/";
+
+/// Content that shows a page rendered when the field map names no sections.
+static HEADING_XPATH: &str = "//h1";
+
+/// A top-level heading saying "not found", which the marketplace shows in
+/// place of a product that doesn't exist.
+static NOT_FOUND_XPATH: &str = "//*[self::h1 or self::h2][contains(translate(normalize-space(.),'NOTFUD','notfud'),'not found')]";
+
+/// Quotes `s` as an XPath string literal.
+fn xpath_literal(s: &str) -> String {
+    if !s.contains('\'') {
+        format!("'{}'", s)
+    } else if !s.contains('"') {
+        format!("\"{}\"", s)
+    } else {
+        let parts: Vec<String> = s.split('\'').map(|part| format!("'{}'", part)).collect();
+        format!("concat({})", parts.join(", \"'\", "))
+    }
+}
+
+/// Every section `fields` reads from, found by its `<h3>` heading the same
+/// way the HTML parser finds it. `None` if the map names no sections.
+fn section_xpath(fields: &FieldMap) -> Option<String> {
+    let headings = fields.sections();
+    if headings.is_empty() {
+        return None;
+    }
+    let tests: Vec<String> = headings
+        .iter()
+        .map(|heading| format!("contains(text(),{})", xpath_literal(heading)))
+        .collect();
+    Some(format!("//h3[{}]/parent::div", tests.join(" or ")))
+}
+
+/// A section only counts as rendered once it has some text besides its
+/// heading.
+fn ready_xpath(fields: &FieldMap) -> String {
+    match section_xpath(fields) {
+        Some(xpath) => format!("{}[normalize-space(.) != normalize-space(h3)]", xpath),
+        None => HEADING_XPATH.to_string(),
+    }
+}
+
+/// How long to wait for the product page to render after navigating.
+#[derive(Debug, Clone, Copy)]
+pub struct WaitConfig {
+    pub timeout: Duration,
+    pub interval: Duration,
+}
+
+/// Reads the product page that is currently loaded in `driver`. The page
+/// source is parsed exactly like a saved page, so the browser and offline
+/// backends agree, and the whole page is read in one round trip.
+async fn get_product(
+    driver: &WebDriver,
+    id: &str,
+    fields: &FieldMap,
+) -> Result<Product, ScrapeError> {
+    let source = driver.source().await.map_err(|e| {
+        ScrapeError::new(
+            ErrorKind::Navigation,
+            format!("Could not read page source: {}", e),
+        )
+    })?;
+    html::parse_product_page(id, &source, fields)
+}
+
+/// Works out why the configured sections never rendered: one is there but
+/// empty, the page rendered without any, or the page never rendered.
+async fn classify_timeout(driver: &WebDriver, wait: &WaitConfig, fields: &FieldMap) -> ScrapeError {
+    let names = html::section_names(&fields.sections());
+    if let Some(xpath) = section_xpath(fields) {
+        if driver.find(By::XPath(xpath)).await.is_ok() {
+            return ScrapeError::new(
+                ErrorKind::EmptySection,
+                format!("{} section stayed empty", names),
+            );
+        }
+        if driver.find(By::Tag("h1")).await.is_ok() {
+            return ScrapeError::new(
+                ErrorKind::SectionNotFound,
+                format!("{} section not found", names),
+            );
+        }
+    }
+    ScrapeError::new(
+        ErrorKind::Timeout,
+        format!(
+            "Timed out after {:.1}s waiting for the page to render",
+            wait.timeout.as_secs_f64()
+        ),
+    )
+}
+
+/// Waits until either a section the field map reads from is populated or the
+/// page says the product was not found.
+async fn wait_for_page(
+    driver: &WebDriver,
+    wait: &WaitConfig,
+    fields: &FieldMap,
+) -> WebDriverResult<WebElement> {
+    driver
+        .query(By::XPath(format!(
+            "{} | {}",
+            ready_xpath(fields),
+            NOT_FOUND_XPATH
+        )))
+        .wait(wait.timeout, wait.interval)
+        .first()
+        .await
+}
+
+/// Checks where the browser ended up. Returns the redirect if it landed on a
+/// different product, or a not-found error if it left the product pages or
+/// the page says the product doesn't exist.
+async fn check_location(driver: &WebDriver, id: &str) -> Result<Option<Redirect>, ScrapeError> {
+    if driver.find(By::XPath(NOT_FOUND_XPATH)).await.is_ok() {
+        return Err(ScrapeError::new(
+            ErrorKind::NotFound,
+            format!("The marketplace has no product with ID {}", id),
+        ));
+    }
+
+    let Ok(url) = driver.current_url().await else {
+        return Ok(None);
+    };
+    match discover::product_id_from_href(url.as_str()) {
+        Some(final_id) if final_id.eq_ignore_ascii_case(id) => Ok(None),
+        Some(final_id) => Ok(Some(Redirect {
+            id: final_id,
+            url: url.to_string(),
+        })),
+        None => Err(ScrapeError::new(
+            ErrorKind::NotFound,
+            format!(
+                "ID {} redirected to {}, which is not a product page",
+                id, url
+            ),
+        )),
+    }
+}
+
+/// Saves the page source and the first section the field map reads from
+/// (normally Authorization Details), if there is one. Failures only warn,
+/// since the scrape itself may still succeed.
+async fn archive_page(driver: &WebDriver, id: &str, archive: &Archive, fields: &FieldMap) {
+    let result: Result<(), Box<dyn Error + Send + Sync>> = async {
+        let page = driver.source().await?;
+        let section = match section_xpath(fields) {
+            Some(xpath) => match driver.find(By::XPath(xpath)).await {
+                Ok(section) => Some(section.outer_html().await?),
+                Err(_) => None,
+            },
+            None => None,
+        };
+
+        let mut files = vec![("page.html", page.as_str())];
+        if let Some(section) = &section {
+            files.push(("auth.html", section.as_str()));
+        }
+        archive.save(id, &files)?;
+        Ok(())
+    }
+    .await;
+
+    if let Err(e) = result {
+        eprintln!("Warning: could not archive page for ID {}: {}", id, e);
+    }
+}
+
+/// Loads the product page for `id`, waits for a section the field map reads
+/// from (Authorization Details by default) to be populated, and scrapes it. The page is refreshed once if it
+/// doesn't render within the timeout. IDs the marketplace doesn't know fail
+/// as not found; IDs that redirect to another product are scraped from there
+/// and the redirect is recorded. With an `archive`, the page is saved as it
+/// was once loaded, whether or not it rendered.
+pub async fn scrape_product(
+    driver: &WebDriver,
+    id: &str,
+    wait: &WaitConfig,
+    fields: &FieldMap,
+    archive: Option<&Archive>,
+) -> Result<Product, ScrapeError> {
+    driver
+        .goto(format!("{}{}", URL_BASE, id))
+        .await
+        .map_err(|e| {
+            ScrapeError::new(ErrorKind::Navigation, format!("Navigation failed: {}", e))
+        })?;
+
+    let mut rendered = wait_for_page(driver, wait, fields).await;
+    if rendered.is_err() {
+        eprintln!(
+            "Page for ID {} did not render within {:.1}s; refreshing",
+            id,
+            wait.timeout.as_secs_f64()
+        );
+        driver.refresh().await.map_err(|e| {
+            ScrapeError::new(ErrorKind::Navigation, format!("Refresh failed: {}", e))
+        })?;
+        rendered = wait_for_page(driver, wait, fields).await;
+    }
+
+    if let Some(archive) = archive {
+        archive_page(driver, id, archive, fields).await;
+    }
+
+    let redirect = check_location(driver, id).await?;
+    if let Some(redirect) = &redirect {
+        eprintln!("ID {} redirected to product {}", id, redirect.id);
+    }
+    if rendered.is_err() {
+        return Err(classify_timeout(driver, wait, fields).await);
+    }
+
+    let mut product = get_product(driver, id, fields).await?;
+    product.redirect = redirect;
+    Ok(product)
+}
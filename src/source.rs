@@ -0,0 +1,174 @@
+// Copyright 2025 Maya Kaczorowski
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::archive::Archive;
+use crate::data;
+use crate::discover::ListingEntry;
+use crate::error::{ErrorKind, ScrapeError};
+use crate::fields::FieldMap;
+use crate::html;
+use crate::product::Product;
+use crate::scrape::{self, WaitConfig};
+use clap::ValueEnum;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use thirtyfour::prelude::*;
+use tokio::sync::OnceCell;
+
+/// Where the marketplace site itself loads its product data from.
+pub static DEFAULT_DATA_URL: &str =
+    "https://raw.githubusercontent.com/GSA/marketplace-fedramp-gov-data/main/data.json";
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SourceKind {
+    /// Render each product page in a browser over WebDriver.
+    Browser,
+    /// Download the marketplace's backing JSON data; no browser needed.
+    Http,
+}
+
+/// Somewhere product data can be read from, one ID at a time. Each worker in
+/// the pool gets its own clone.
+pub trait ProductSource: Clone + Send + Sync + 'static {
+    fn fetch(&self, id: &str) -> impl Future<Output = Result<Product, ScrapeError>> + Send;
+}
+
+/// Scrapes the live product page through a WebDriver session.
+#[derive(Clone)]
+pub struct WebDriverSource {
+    pub driver: WebDriver,
+    pub wait: WaitConfig,
+    pub fields: Arc<FieldMap>,
+    pub archive: Option<Archive>,
+}
+
+impl ProductSource for WebDriverSource {
+    async fn fetch(&self, id: &str) -> Result<Product, ScrapeError> {
+        scrape::scrape_product(
+            &self.driver,
+            id,
+            &self.wait,
+            &self.fields,
+            self.archive.as_ref(),
+        )
+        .await
+    }
+}
+
+/// Parses product pages saved as `<dir>/<id>.html`.
+#[derive(Clone)]
+pub struct HtmlDirSource {
+    /// Saved page of each product, from [`html::pages_in_dir`].
+    pub pages: Arc<BTreeMap<String, PathBuf>>,
+    pub fields: Arc<FieldMap>,
+}
+
+impl ProductSource for HtmlDirSource {
+    async fn fetch(&self, id: &str) -> Result<Product, ScrapeError> {
+        let path = self.pages.get(id).ok_or_else(|| {
+            ScrapeError::new(
+                ErrorKind::Navigation,
+                format!("No saved page for ID {}", id),
+            )
+        })?;
+        html::parse_product_file(path, id, &self.fields)
+    }
+}
+
+/// Reads products from the marketplace's JSON data file. The file is
+/// downloaded once, on first use, and shared by every clone. With an archive,
+/// each product's entry from the file is saved as it is read.
+#[derive(Clone)]
+pub struct HttpSource {
+    client: reqwest::Client,
+    url: String,
+    products: Arc<OnceCell<Map<String, Value>>>,
+    archive: Option<Archive>,
+}
+
+impl HttpSource {
+    pub fn new(url: &str, archive: Option<Archive>) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let client = reqwest::Client::builder().timeout(FETCH_TIMEOUT).build()?;
+        Ok(HttpSource {
+            client,
+            url: url.to_string(),
+            products: Arc::new(OnceCell::new()),
+            archive,
+        })
+    }
+
+    async fn products(&self) -> Result<&Map<String, Value>, Box<dyn Error + Send + Sync>> {
+        self.products
+            .get_or_try_init(|| async {
+                eprintln!("Downloading product data from {}", self.url);
+                let body: Value = self
+                    .client
+                    .get(&self.url)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+                let products = data::products_by_id(body)?;
+                eprintln!("Loaded {} products", products.len());
+                Ok(products)
+            })
+            .await
+    }
+
+    /// Every product in the data file, in the order it lists them.
+    pub async fn entries(&self) -> Result<Vec<ListingEntry>, Box<dyn Error + Send + Sync>> {
+        Ok(self
+            .products()
+            .await?
+            .iter()
+            .map(|(id, product)| {
+                let overview = data::parse_product(id, product).overview;
+                ListingEntry {
+                    id: id.clone(),
+                    name: overview.offering_name.unwrap_or_default(),
+                    status: overview.status.unwrap_or_default(),
+                }
+            })
+            .collect())
+    }
+}
+
+impl ProductSource for HttpSource {
+    async fn fetch(&self, id: &str) -> Result<Product, ScrapeError> {
+        let product = self
+            .products()
+            .await
+            .map_err(|e| ScrapeError::new(ErrorKind::Navigation, e))?
+            .get(id)
+            .ok_or_else(|| {
+                ScrapeError::new(
+                    ErrorKind::NotFound,
+                    format!("ID {} not found in {}", id, self.url),
+                )
+            })?;
+        if let Some(archive) = &self.archive
+            && let Err(e) = archive.save(id, &[("data.json", &format!("{:#}", product))])
+        {
+            eprintln!("Warning: could not archive data for ID {}: {}", id, e);
+        }
+        Ok(data::parse_product(id, product))
+    }
+}